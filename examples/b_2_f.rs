@@ -2,8 +2,9 @@ use std::{fs::File, io::Write, sync::atomic::AtomicU64};
 
 use chrono::Utc;
 use phases::{
-    logs::CsvLogger, run_python, Array2d, BinAtom as Atom, BinConcentration as Concentration,
-    Energies, StreamingStats, System,
+    ln_multinomial, logs::CsvLogger, run_python, Array2d, BinAtom as Atom,
+    BinConcentration as Concentration, DensityOfStates, Energies, EnergyHistogram, PairCorrelation,
+    StreamingStats, System,
 };
 use rayon::prelude::*;
 
@@ -12,13 +13,25 @@ const WIDTH: usize = 32;
 const HEIGHT: usize = 64;
 const STEPS: usize = WIDTH * HEIGHT * 4000;
 const FIRST_STEPS: usize = WIDTH * HEIGHT * 4000;
-const EQUILIBRIUM_STEPS: usize = WIDTH * HEIGHT * 10_000;
+const MAX_EQUILIBRIUM_STEPS: usize = WIDTH * HEIGHT * 10_000;
+const SAMPLE_INTERVAL: usize = WIDTH * HEIGHT;
 
 const ENERGIES: [f32; 4] = [-1.0, -0.75, -0.75, -1.0];
 
 // temp
 const TEMP_STEPS: usize = 15;
 const START_TEMP: f32 = 2.0;
+// the temperatures the histograms are reweighted to
+const REWEIGHTED_TEMP_STEPS: usize = 200;
+
+// reweighting
+const BIN_WIDTH: f32 = 0.25;
+const TOLERANCE: f64 = 1e-7;
+
+// correlation length
+const CORRELATION_INTERVAL: usize = WIDTH * HEIGHT * 100;
+const MAX_DISTANCE: f32 = 16.0;
+const DISTANCE_BIN_WIDTH: f32 = 0.25;
 
 // concentration
 const CONCENTRATION_STEPS: usize = 8;
@@ -31,7 +44,8 @@ fn main() {
 
     make_system_file(&name).unwrap();
 
-    let temps: Vec<f32> = (0..TEMP_STEPS)
+    // the grid of 0..TEMP_STEPS without T = 0, the histograms need a finite beta
+    let temps: Vec<f32> = (1..TEMP_STEPS)
         .map(|i| START_TEMP / TEMP_STEPS as f32 * i as f32)
         .rev()
         .collect();
@@ -39,68 +53,135 @@ fn main() {
         .map(|i| (i as f64 / (CONCENTRATION_STEPS - 1) as f64) * 0.92 + 0.04)
         .collect();
 
-    let (logger, handle) =
-        CsvLogger::new(
-            format!("out/logs/{}.csv", name),
-            "file generated as log to github maxkay/phases".to_owned(),
-            vec![
-                "c".to_owned(),
-                "temp".to_owned(),
-                "energy".to_owned(),
-                "heat capacity".to_owned(),
-            ],
-        );
+    let (logger, handle) = CsvLogger::new(
+        format!("out/logs/{}.csv", name),
+        "file generated as log to github maxkay/phases".to_owned(),
+        vec![
+            "c".to_owned(),
+            "temp".to_owned(),
+            "energy".to_owned(),
+            "heat capacity".to_owned(),
+        ],
+    );
+    let (sample_logger, sample_handle) = CsvLogger::new(
+        format!("out/logs/{}_samples.csv", name),
+        "file generated as log to github maxkay/phases".to_owned(),
+        vec![
+            "c".to_owned(),
+            "temp".to_owned(),
+            "energy".to_owned(),
+            "energy error".to_owned(),
+            "autocorrelation time".to_owned(),
+            "equilibration steps".to_owned(),
+            "correlation length".to_owned(),
+        ],
+    );
 
     let _: Vec<_> = concentrations
         .par_iter()
-        .map_with(logger, |logger, c_a| {
+        .map_with((logger, sample_logger), |(logger, sample_logger), c_a| {
             run_model_with_concentration(
                 Concentration::new(*c_a, 1.0 - c_a),
                 temps.clone(),
                 logger.clone(),
+                sample_logger.clone(),
             )
         })
         .collect();
-    handle
-        .join()
-        .expect("failed to log")
-        .expect("failed to log");
+    for handle in [handle, sample_handle] {
+        handle
+            .join()
+            .expect("failed to log")
+            .expect("failed to log");
+    }
     println!("finished running {}, took {:?}", name, start.elapsed());
 
     run_python("python/b_f.py", &name)
 }
 
-fn run_model_with_concentration(concentration: Concentration, temps: Vec<f32>, logger: CsvLogger) {
+fn run_model_with_concentration(
+    concentration: Concentration,
+    temps: Vec<f32>,
+    logger: CsvLogger,
+    sample_logger: CsvLogger,
+) {
     let mut system = System::<Array2d<Atom, WIDTH, HEIGHT>, _>::new(ENERGIES, None, concentration);
     for _ in 0..FIRST_STEPS {
         system.move_vacancy(1.0 / temps[0]);
     }
-    for temp in temps {
+    let mut histograms = Vec::new();
+    for temp in temps.iter().copied() {
         let beta = 1.0 / temp;
 
-        for _ in 0..EQUILIBRIUM_STEPS {
-            system.move_vacancy(beta);
-        }
+        let equilibration_steps = system.equilibrate(
+            beta,
+            |system, beta| {
+                system.move_vacancy(beta);
+            },
+            SAMPLE_INTERVAL,
+            MAX_EQUILIBRIUM_STEPS,
+        );
 
         let mut stats = StreamingStats::new();
-        for _ in 0..STEPS {
+        let mut histogram = EnergyHistogram::new(beta, BIN_WIDTH);
+        let mut pair_correlation: Option<PairCorrelation<Atom>> = None;
+        for i in 1..=STEPS {
             system.move_vacancy(beta);
-            stats.add_value(system.internal_energy())
+            stats.add_value(system.internal_energy());
+            histogram.add_value(system.internal_energy());
+            if i % CORRELATION_INTERVAL == 0 {
+                let new = system.pair_correlation(MAX_DISTANCE, DISTANCE_BIN_WIDTH);
+                match pair_correlation.as_mut() {
+                    Some(pair_correlation) => pair_correlation.combine(&new),
+                    None => pair_correlation = Some(new),
+                }
+            }
         }
+        histograms.push(histogram);
+        let correlation_length = pair_correlation
+            .expect("the correlations are measured at least once")
+            .correlation_length(Atom::new(0), Atom::new(0));
 
-        logger
+        let summary = stats.summary();
+        sample_logger
             .send_row(vec![
                 concentration.get_c_a() as f32,
                 temp,
-                stats.avg() / (WIDTH * HEIGHT) as f32,
-                stats.variance() / (temp * temp) / (WIDTH * HEIGHT) as f32,
+                summary.mean() / (WIDTH * HEIGHT) as f32,
+                summary.error() / (WIDTH * HEIGHT) as f32,
+                summary.autocorrelation_time(),
+                equilibration_steps as f32 / (WIDTH * HEIGHT) as f32,
+                correlation_length,
             ])
             .unwrap();
 
         let progress = PROGRESS_COUNTER.fetch_add(1, std::sync::atomic::Ordering::AcqRel);
-        println!("{} of {}", progress, TEMP_STEPS * CONCENTRATION_STEPS);
+        println!("{} of {}", progress, (TEMP_STEPS - 1) * CONCENTRATION_STEPS);
     }
-    std::mem::drop(logger)
+
+    let density_of_states = match DensityOfStates::from_histograms(&histograms, TOLERANCE) {
+        Ok(density_of_states) => density_of_states,
+        Err(err) => {
+            println!("c_a = {}: {}", concentration.get_c_a(), err);
+            return;
+        }
+    };
+    let ln_states = ln_multinomial(&system.species_counts());
+    let (min_temp, max_temp) = (temps[temps.len() - 1], temps[0]);
+    for i in (0..REWEIGHTED_TEMP_STEPS).rev() {
+        let temp = min_temp + (max_temp - min_temp) / (REWEIGHTED_TEMP_STEPS - 1) as f32 * i as f32;
+        let thermodynamics = density_of_states.thermodynamics(temp, ln_states);
+        logger
+            .send_row(vec![
+                concentration.get_c_a() as f32,
+                temp,
+                thermodynamics.energy() / (WIDTH * HEIGHT) as f32,
+                thermodynamics.heat_capacity() / (WIDTH * HEIGHT) as f32,
+            ])
+            .unwrap();
+    }
+    std::mem::drop(logger);
+    std::mem::drop(sample_logger)
 }
 
 fn make_system_file(name: &String) -> Result<(), Box<dyn std::error::Error>> {
@@ -112,15 +193,25 @@ fn make_system_file(name: &String) -> Result<(), Box<dyn std::error::Error>> {
     writeln!(file, "{}", energies_dict)?;
     writeln!(file, "width, height")?;
     writeln!(file, "{}, {}", WIDTH, HEIGHT)?;
-    writeln!(file, "steps_per_site: for equilibrium, for measurement")?;
+    writeln!(
+        file,
+        "steps_per_site: at most for equilibrium, for measurement"
+    )?;
     writeln!(
         file,
         "{},{}",
-        EQUILIBRIUM_STEPS / WIDTH / HEIGHT,
+        MAX_EQUILIBRIUM_STEPS / WIDTH / HEIGHT,
         STEPS / WIDTH / HEIGHT
     )?;
-    writeln!(file, "start temp, temp steps")?;
-    writeln!(file, "{}, {}", START_TEMP, TEMP_STEPS)?;
+    writeln!(file, "start temp, temp steps, reweighted temp steps")?;
+    writeln!(
+        file,
+        "{}, {}, {}",
+        START_TEMP, TEMP_STEPS, REWEIGHTED_TEMP_STEPS
+    )?;
+    writeln!(file, "T = 0 is left out of the temp steps")?;
+    writeln!(file, "bin width")?;
+    writeln!(file, "{}", BIN_WIDTH)?;
     writeln!(file, "concentration steps")?;
     writeln!(file, "{}", CONCENTRATION_STEPS)?;
     Ok(())
@@ -0,0 +1,124 @@
+use std::{fs::File, io::Write};
+
+use chrono::Utc;
+use phases::{
+    logs::CsvLogger, BinAtom as Atom, BinConcentration as Concentration, DynArray2d, Energies,
+    OrderParameter, OrderParameterMoments, Size2d, StreamingStats, System,
+};
+use rayon::prelude::*;
+
+// model parameters
+// unlike neighbors are favored, so the atoms order on a checkerboard
+const ENERGIES: [f32; 4] = [-0.75, -1.0, -1.0, -0.75];
+// the side lengths have to be even for the checkerboard
+const SIDES: [usize; 4] = [8, 16, 32, 64];
+const SWEEPS: usize = 20_000;
+const MAX_EQUILIBRIUM_SWEEPS: usize = 10_000;
+
+// temp
+const TEMP_STEPS: usize = 16;
+const MIN_TEMP: f32 = 0.15;
+const MAX_TEMP: f32 = 0.45;
+
+fn main() {
+    let start = std::time::Instant::now();
+    let name = format!("b_2_o_{}", Utc::now().format("%Y-%m-%d_%H-%M"));
+
+    make_system_file(&name).unwrap();
+
+    let temps: Vec<f32> = (0..TEMP_STEPS)
+        .map(|i| MIN_TEMP + (MAX_TEMP - MIN_TEMP) / (TEMP_STEPS - 1) as f32 * i as f32)
+        .rev()
+        .collect();
+
+    let mut categories = vec![
+        "side".to_owned(),
+        "temp".to_owned(),
+        "energy".to_owned(),
+        "energy error".to_owned(),
+        "susceptibility".to_owned(),
+    ];
+    categories.append(&mut OrderParameterMoments::get_categories(Some(
+        "checkerboard ",
+    )));
+    let (logger, handle) = CsvLogger::new(
+        format!("out/logs/{}.csv", name),
+        "file generated as log to github maxkay/phases".to_owned(),
+        categories,
+    );
+
+    let _: Vec<_> = SIDES
+        .par_iter()
+        .map_with(logger, |logger, side| {
+            run_model_with_side(*side, temps.clone(), logger.clone())
+        })
+        .collect();
+    handle
+        .join()
+        .expect("failed to log")
+        .expect("failed to log");
+    println!("finished running {}, took {:?}", name, start.elapsed());
+}
+
+/// The Binder cumulants of the different sides cross at the critical temperature.
+fn run_model_with_side(side: usize, temps: Vec<f32>, logger: CsvLogger) {
+    let sites = side * side;
+    let mut system = System::<DynArray2d<Atom>, _>::with_shape(
+        ENERGIES,
+        None,
+        Concentration::new(1.0, 1.0),
+        Size2d {
+            width: side,
+            height: side,
+        },
+    );
+    let order_parameter = OrderParameter::checkerboard(system.lattice(), Atom::new(0));
+    for temp in temps {
+        let beta = 1.0 / temp;
+        system.equilibrate(
+            beta,
+            |system, beta| {
+                system.local_swap(beta);
+            },
+            sites,
+            MAX_EQUILIBRIUM_SWEEPS * sites,
+        );
+
+        let mut energy = StreamingStats::new();
+        let mut moments = OrderParameterMoments::new();
+        for _ in 0..SWEEPS {
+            for _ in 0..sites {
+                system.local_swap(beta);
+            }
+            energy.add_value(system.internal_energy());
+            moments.add_value(system.order_parameter(&order_parameter));
+        }
+
+        let mut values = vec![
+            side as f32,
+            temp,
+            energy.avg() / sites as f32,
+            energy.error() / sites as f32,
+            moments.susceptibility(beta, sites),
+        ];
+        values.append(&mut moments.as_vec_f32());
+        logger.send_row(values).expect("error while sending row");
+        println!("side {} temp {} done", side, temp);
+    }
+}
+
+fn make_system_file(name: &String) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = File::create(format!("out/systems/{}.txt", name))?;
+    let energies_dict = ENERGIES.as_dict();
+
+    writeln!(file, "{}", name)?;
+    writeln!(file, "energies:")?;
+    writeln!(file, "{}", energies_dict)?;
+    writeln!(file, "sides")?;
+    writeln!(file, "{:?}", SIDES)?;
+    writeln!(file, "sweeps: at most for equilibrium, for measurement")?;
+    writeln!(file, "{},{}", MAX_EQUILIBRIUM_SWEEPS, SWEEPS)?;
+    writeln!(file, "min temp, max temp, temp steps")?;
+    writeln!(file, "{}, {}, {}", MIN_TEMP, MAX_TEMP, TEMP_STEPS)?;
+    Ok(())
+}
@@ -4,8 +4,10 @@ use chrono::Utc;
 use phases::{
     anim::{self, prepare_file_encoder},
     logs::CsvLogger,
-    run_python, BinAtom as Atom, BinConcentration as Concentration, ClusterStats, Energies,
-    FastArray, System,
+    run_python,
+    schedule::Exponential,
+    BinAtom as Atom, BinConcentration as Concentration, ClusterStats, Energies, FastArray,
+    OrderParameter, ShortRangeOrder, System,
 };
 // model parameters
 const SIDE: usize = 256;
@@ -16,9 +18,6 @@ const ENERGIES: [f32; 4] = [-1.0, -0.75, -0.75, -1.0];
 // temperature
 const START: f32 = 8.0;
 const END: f32 = 0.01;
-fn temp(i: usize) -> f32 {
-    START * ((END / START).ln() / STEPS as f32 * i as f32).exp()
-}
 
 // gif
 const FRAMES: usize = 60;
@@ -38,9 +37,22 @@ fn main() {
 
     let path = format!("out/logs/{}.csv", name);
 
-    let mut categories = vec!["step".to_owned(), "temp".to_owned(), "energy".to_owned()];
+    let mut system =
+        System::<FastArray<Atom, SIDE, POW>, _>::new(ENERGIES, Some("my_seed"), concentration);
+
+    let mut categories = vec![
+        "step".to_owned(),
+        "temp".to_owned(),
+        "energy".to_owned(),
+        "domain size".to_owned(),
+        "checkerboard order".to_owned(),
+    ];
     categories.append(&mut ClusterStats::get_categories(Some("atom 0 ")));
     categories.append(&mut ClusterStats::get_categories(Some("atom 2 ")));
+    categories.append(&mut ShortRangeOrder::<Atom>::get_categories(
+        None::<&str>,
+        system.short_range_order().shells(),
+    ));
 
     let (logger, handle) = CsvLogger::new(
         path,
@@ -48,6 +60,9 @@ fn main() {
         categories,
     );
 
+    // like atoms attract each other, so this stays close to 0 while the atoms separate
+    let order_parameter = OrderParameter::checkerboard(system.lattice(), Atom::new(0));
+
     let mut encoder =
         prepare_file_encoder(
             format!("out/gifs/{}.gif", name),
@@ -57,33 +72,38 @@ fn main() {
             anim::PALETTE,
         );
 
-    let mut system =
-        System::<FastArray<Atom, SIDE, POW>, _>::new(ENERGIES, Some("my_seed"), concentration);
-
-    for i in 0..STEPS {
-        system.move_vacancy(1.0 / temp(i));
-        if i % (STEPS / LOG_ENTRIES) == 0 {
-            let mut values = vec![
-                i as f32 / (SIDE * SIDE) as f32,
-                temp(i),
-                system.internal_energy() / (SIDE * SIDE) as f32,
-            ];
-            for distr in system.count_all_clusters() {
-                let c_stats = ClusterStats::from_map_atom(&distr);
-                if !c_stats.valid() {
-                    println!("{:?}", c_stats);
+    system.anneal(
+        &Exponential::new(START, END, STEPS),
+        |system, beta| {
+            system.move_vacancy(beta);
+        },
+        &mut [
+            (STEPS / LOG_ENTRIES, &mut |system, i, temp| {
+                let mut values = vec![
+                    i as f32 / (SIDE * SIDE) as f32,
+                    temp,
+                    system.internal_energy() / (SIDE * SIDE) as f32,
+                    system.structure_factor(Atom::new(0)).domain_size(),
+                    system.order_parameter(&order_parameter),
+                ];
+                for distr in system.count_all_clusters() {
+                    let c_stats = ClusterStats::from_map_atom(&distr);
+                    if !c_stats.valid() {
+                        println!("{:?}", c_stats);
+                    }
+                    values.append(&mut c_stats.as_vec_f32());
                 }
-                values.append(&mut c_stats.as_vec_f32());
-            }
-            logger.send_row(values).expect("error while sending row");
-        }
-        if i % (STEPS / FRAMES) == 0 {
-            let frame = system.get_frame();
-            encoder
-                .write_frame(&frame)
-                .expect("Error while writing frame!");
-        }
-    }
+                values.append(&mut system.short_range_order().as_vec_f32());
+                logger.send_row(values).expect("error while sending row");
+            }),
+            (STEPS / FRAMES, &mut |system, _, _| {
+                let frame = system.get_frame();
+                encoder
+                    .write_frame(&frame)
+                    .expect("Error while writing frame!");
+            }),
+        ],
+    );
 
     std::mem::drop(logger);
     if let Err(err) = handle.join() {
@@ -2,8 +2,8 @@ use std::{fs::File, io::Write, mem::drop, sync::atomic::AtomicU64};
 
 use chrono::Utc;
 use phases::{
-    logs::CsvLogger, run_python, Array3d, BinAtom as Atom, BinConcentration as Concentration,
-    Energies, StreamingStats, System,
+    ln_multinomial, logs::CsvLogger, run_python, Array3d, BinAtom as Atom,
+    BinConcentration as Concentration, DensityOfStates, Energies, System,
 };
 use rayon::prelude::*;
 // model parameters
@@ -18,6 +18,14 @@ const ENERGIES: [f32; 4] = [-1.0, -0.75, -0.75, -1.0];
 // temp
 const TEMP_STEPS: usize = 100;
 const START_TEMP: f32 = 150.0;
+// the temperatures the histograms are reweighted to
+const REWEIGHTED_TEMP_STEPS: usize = 400;
+
+// reweighting
+const SAMPLE_INTERVAL: usize = 64;
+// the energies are multiples of 0.25, so every bin holds a single energy
+const BIN_WIDTH: f32 = 0.25;
+const TOLERANCE: f64 = 1e-7;
 
 // concentration
 const CONCENTRATION_STEPS: usize = 30;
@@ -30,7 +38,8 @@ fn main() {
 
     make_system_file(&name).unwrap();
 
-    let temps: Vec<f32> = (0..TEMP_STEPS)
+    // the grid of 0..TEMP_STEPS without T = 0, the histograms need a finite beta
+    let temps: Vec<f32> = (1..TEMP_STEPS)
         .map(|i| START_TEMP / TEMP_STEPS as f32 * i as f32)
         .rev()
         .collect();
@@ -72,30 +81,48 @@ fn main() {
 fn run_model_with_concentration(concentration: Concentration, temps: Vec<f32>, logger: CsvLogger) {
     let mut system =
         System::<Array3d<Atom, WIDTH, HEIGHT, DEPTH>, _>::new(ENERGIES, None, concentration);
-    for temp in temps {
+    let mut histograms = Vec::new();
+    for temp in temps.iter().copied() {
         let beta = 1.0 / temp;
 
         for _ in 0..EQUILIBRIUM_STEPS {
             system.move_vacancy(beta);
         }
 
-        let mut stats = StreamingStats::new();
-        for _ in 0..STEPS {
-            system.move_vacancy(beta);
-            stats.add_value(system.internal_energy())
-        }
+        histograms.push(system.record_energy_histogram(
+            beta,
+            |system, beta| {
+                system.move_vacancy(beta);
+            },
+            STEPS,
+            SAMPLE_INTERVAL,
+            BIN_WIDTH,
+        ));
+
+        let progress = PROGRESS_COUNTER.fetch_add(1, std::sync::atomic::Ordering::AcqRel);
+        println!("{} of {}", progress, (TEMP_STEPS - 1) * CONCENTRATION_STEPS);
+    }
 
+    let density_of_states = match DensityOfStates::from_histograms(&histograms, TOLERANCE) {
+        Ok(density_of_states) => density_of_states,
+        Err(err) => {
+            println!("c_a = {}: {}", concentration.get_c_a(), err);
+            return;
+        }
+    };
+    let ln_states = ln_multinomial(&system.species_counts());
+    let (min_temp, max_temp) = (temps[temps.len() - 1], temps[0]);
+    for i in (0..REWEIGHTED_TEMP_STEPS).rev() {
+        let temp = min_temp + (max_temp - min_temp) / (REWEIGHTED_TEMP_STEPS - 1) as f32 * i as f32;
+        let thermodynamics = density_of_states.thermodynamics(temp, ln_states);
         logger
             .send_row(vec![
                 concentration.get_c_a() as f32,
                 temp,
-                stats.avg() / (WIDTH * HEIGHT * DEPTH) as f32,
-                stats.variance() / (temp * temp) / (WIDTH * HEIGHT * DEPTH) as f32,
+                thermodynamics.energy() / (WIDTH * HEIGHT * DEPTH) as f32,
+                thermodynamics.heat_capacity() / (WIDTH * HEIGHT * DEPTH) as f32,
             ])
             .unwrap();
-
-        let progress = PROGRESS_COUNTER.fetch_add(1, std::sync::atomic::Ordering::AcqRel);
-        println!("{} of {}", progress, TEMP_STEPS * CONCENTRATION_STEPS);
     }
     drop(logger)
 }
@@ -116,8 +143,15 @@ fn make_system_file(name: &String) -> Result<(), Box<dyn std::error::Error>> {
         EQUILIBRIUM_STEPS / WIDTH / HEIGHT / DEPTH,
         STEPS / WIDTH / HEIGHT / DEPTH
     )?;
-    writeln!(file, "start temp, temp steps")?;
-    writeln!(file, "{}, {}", START_TEMP, TEMP_STEPS)?;
+    writeln!(file, "start temp, temp steps, reweighted temp steps")?;
+    writeln!(
+        file,
+        "{}, {}, {}",
+        START_TEMP, TEMP_STEPS, REWEIGHTED_TEMP_STEPS
+    )?;
+    writeln!(file, "T = 0 is left out of the temp steps")?;
+    writeln!(file, "sample interval, bin width")?;
+    writeln!(file, "{}, {}", SAMPLE_INTERVAL, BIN_WIDTH)?;
     writeln!(file, "concentration steps")?;
     writeln!(file, "{}", CONCENTRATION_STEPS)?;
     Ok(())
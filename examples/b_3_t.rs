@@ -5,8 +5,9 @@ use chrono::Utc;
 use phases::{
     anim::{self, prepare_file_encoder},
     logs::CsvLogger,
-    run_python, Array3d, BinAtom as Atom, BinConcentration as Concentration, ClusterStats,
-    Energies, System,
+    run_python,
+    schedule::Exponential,
+    Array3d, BinAtom as Atom, BinConcentration as Concentration, ClusterStats, Energies, System,
 };
 // model parameters
 const WIDTH: usize = 64;
@@ -19,9 +20,6 @@ const ENERGIES: [f32; 4] = [-1.0, -0.75, -0.75, -1.0];
 // temperature
 const START: f32 = 150.0;
 const END: f32 = 0.01;
-fn temp(i: usize) -> f32 {
-    START * ((END / START).ln() / STEPS as f32 * i as f32).exp()
-}
 
 // gif
 const FRAMES: usize = 60;
@@ -65,26 +63,31 @@ fn main() {
             concentration,
         );
 
-    for i in 0..STEPS {
-        system.move_vacancy(1.0 / temp(i));
-        if i % (STEPS / LOG_ENTRIES) == 0 {
-            let mut values = vec![
-                i as f32 / (WIDTH * HEIGHT * DEPTH) as f32,
-                temp(i),
-                system.internal_energy() / (WIDTH * HEIGHT * DEPTH) as f32,
-            ];
-            for distr in system.count_all_clusters() {
-                values.append(&mut ClusterStats::from_map_atom(&distr).as_vec_f32());
-            }
-            logger.send_row(values).expect("error while sending row");
-        }
-        if i % (STEPS / FRAMES) == 0 {
-            let frame = system.get_frame();
-            encoder
-                .write_frame(&frame)
-                .expect("Error while writing frame!");
-        }
-    }
+    system.anneal(
+        &Exponential::new(START, END, STEPS),
+        |system, beta| {
+            system.move_vacancy(beta);
+        },
+        &mut [
+            (STEPS / LOG_ENTRIES, &mut |system, i, temp| {
+                let mut values = vec![
+                    i as f32 / (WIDTH * HEIGHT * DEPTH) as f32,
+                    temp,
+                    system.internal_energy() / (WIDTH * HEIGHT * DEPTH) as f32,
+                ];
+                for distr in system.count_all_clusters() {
+                    values.append(&mut ClusterStats::from_map_atom(&distr).as_vec_f32());
+                }
+                logger.send_row(values).expect("error while sending row");
+            }),
+            (STEPS / FRAMES, &mut |system, _, _| {
+                let frame = system.get_frame();
+                encoder
+                    .write_frame(&frame)
+                    .expect("Error while writing frame!");
+            }),
+        ],
+    );
 
     std::mem::drop(logger);
     if let Err(err) = handle.join() {
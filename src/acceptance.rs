@@ -0,0 +1,38 @@
+use std::{fmt, sync::Arc};
+
+/// The rule by which the moves of a `System` are accepted.
+#[derive(Clone, Default)]
+pub enum AcceptanceRule {
+    /// accepted with min(1, exp(-beta * delta_e))
+    #[default]
+    Metropolis,
+    /// heat bath, accepted with 1 / (1 + exp(beta * delta_e))
+    Glauber,
+    /// accepted with the probability returned by the function for beta and delta_e
+    Custom(Arc<dyn Fn(f32, f32) -> f32 + Send + Sync>),
+}
+
+impl AcceptanceRule {
+    pub fn custom(probability: impl Fn(f32, f32) -> f32 + Send + Sync + 'static) -> Self {
+        Self::Custom(Arc::new(probability))
+    }
+
+    /// the probability to accept a move with the change of energy delta_e
+    pub fn probability(&self, beta: f32, delta_e: f32) -> f32 {
+        match self {
+            Self::Metropolis => (-beta * delta_e).exp().min(1.0),
+            Self::Glauber => 1.0 / (1.0 + (beta * delta_e).exp()),
+            Self::Custom(probability) => probability(beta, delta_e),
+        }
+    }
+}
+
+impl fmt::Debug for AcceptanceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Metropolis => write!(f, "Metropolis"),
+            Self::Glauber => write!(f, "Glauber"),
+            Self::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
@@ -6,12 +6,15 @@ use std::{
 use itertools::Itertools;
 use rand::Rng;
 
-use crate::{BinAtom, GifFrame, Lattice, MyRng, RandAtom};
+use crate::{BinAtom, Boundary, Geometry, GifFrame, Grid, Lattice, MyRng, NeighborList, RandAtom};
 
 /// A 2D grid type that is Copy and allows indexes to "wrap around"
+/// The boundaries along x and y can be changed with the shape of the lattice,
+/// along axes which aren't periodic the indexes must not leave the grid.
 #[derive(Clone)]
 pub struct Array2d<T, const W: usize, const H: usize> {
     pub grid: Box<[[T; W]; H]>,
+    boundaries: [Boundary<T>; 2],
 }
 
 impl<T, const W: usize, const H: usize> Array2d<T, W, H>
@@ -24,10 +27,20 @@ where
     {
         Self {
             grid: Box::new([[T::default(); W]; H]),
+            boundaries: [Boundary::Periodic; 2],
         }
     }
+
+    /// This function returns false for indexes which leave the grid along a non periodic axis.
+    fn is_site(&self, idx: (isize, isize)) -> bool {
+        (self.boundaries[0].is_periodic() || (0..W as isize).contains(&idx.0))
+            && (self.boundaries[1].is_periodic() || (0..H as isize).contains(&idx.1))
+    }
 }
 
+/// For every neighbor shell beyond the nearest neighbors one offset of each pair (o, -o).
+pub(crate) const SHELL_OFFSETS: [&[(isize, isize)]; 2] = [&[(1, 1), (1, -1)], &[(2, 0), (0, 2)]];
+
 impl<T, const W: usize, const H: usize> Index<(isize, isize)> for Array2d<T, W, H> {
     type Output = T;
 
@@ -67,16 +80,20 @@ impl<T: Default + Copy, const W: usize, const H: usize> Default for Array2d<T, W
 impl<T: Copy + RandAtom, const W: usize, const H: usize> Lattice for Array2d<T, W, H> {
     type Atom = T;
     type Index = (isize, isize);
-    type Neighbors = [Self::Index; 4];
+    type Neighbors = NeighborList<Self::Index, 4>;
+    /// the boundaries along x and y
+    type Shape = [Boundary<T>; 2];
 
-    fn fill_value(val: Self::Atom) -> Self {
+    fn fill_value(shape: Self::Shape, val: Self::Atom) -> Self {
         Self {
             grid: Box::new([[val; W]; H]),
+            boundaries: shape,
         }
     }
 
-    fn fill_with_fn(func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self {
+    fn fill_with_fn(shape: Self::Shape, func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self {
         let mut out = Self::new();
+        out.boundaries = shape;
         for x in 0..W as isize {
             for y in 0..H as isize {
                 out[(x, y)] = func((x, y));
@@ -85,28 +102,94 @@ impl<T: Copy + RandAtom, const W: usize, const H: usize> Lattice for Array2d<T,
         out
     }
 
+    fn shape(&self) -> Self::Shape {
+        self.boundaries
+    }
+
     fn all_neighbors(&self) -> HashMap<(Self::Atom, Self::Atom), u32> {
         let mut out = HashMap::new();
         for x in 0..W as isize {
             for y in 0..H as isize {
-                out.entry((self[(x, y)], self[(x - 1, y)]))
-                    .and_modify(|e| *e += 1)
-                    .or_insert(1);
-                out.entry((self[(x, y)], self[(x, y - 1)]))
-                    .and_modify(|e| *e += 1)
-                    .or_insert(1);
+                for other in [(x - 1, y), (x, y - 1)] {
+                    if self.is_site(other) {
+                        out.entry((self[(x, y)], self[other]))
+                            .and_modify(|e| *e += 1)
+                            .or_insert(1);
+                    }
+                }
+                for wall in self.wall_neighbors_to((x, y)) {
+                    out.entry((self[(x, y)], wall))
+                        .and_modify(|e| *e += 1)
+                        .or_insert(1);
+                }
             }
         }
         out
     }
 
     fn all_neighbors_to(&self, idx: Self::Index) -> Self::Neighbors {
-        [
+        let mut out = NeighborList::new(idx);
+        for other in [
             (idx.0 + 1, idx.1),
             (idx.0 - 1, idx.1),
             (idx.0, idx.1 + 1),
             (idx.0, idx.1 - 1),
-        ]
+        ] {
+            if self.is_site(other) {
+                out.push(other)
+            }
+        }
+        out
+    }
+
+    fn wall_neighbors_to(&self, idx: Self::Index) -> Vec<Self::Atom> {
+        let mut out = Vec::new();
+        for (i, (coord, len)) in [(idx.0, W), (idx.1, H)].into_iter().enumerate() {
+            if let Boundary::Wall(wall) = self.boundaries[i] {
+                if coord == 0 {
+                    out.push(wall)
+                }
+                if coord == len as isize - 1 {
+                    out.push(wall)
+                }
+            }
+        }
+        out
+    }
+
+    fn shells(&self) -> usize {
+        SHELL_OFFSETS.len() + 1
+    }
+
+    fn all_shell_neighbors(&self, shell: usize) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        if shell == 0 {
+            return self.all_neighbors();
+        }
+        let mut out = HashMap::new();
+        for x in 0..W as isize {
+            for y in 0..H as isize {
+                for (d_x, d_y) in SHELL_OFFSETS[shell - 1] {
+                    let other = (x - d_x, y - d_y);
+                    if self.is_site(other) {
+                        out.entry((self[(x, y)], self[other]))
+                            .and_modify(|e| *e += 1)
+                            .or_insert(1);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn shell_neighbors_to(&self, idx: Self::Index, shell: usize) -> Vec<Self::Index> {
+        if shell == 0 {
+            return self.all_neighbors_to(idx).as_ref().to_vec();
+        }
+        SHELL_OFFSETS[shell - 1]
+            .iter()
+            .flat_map(|(d_x, d_y)| [(idx.0 + d_x, idx.1 + d_y), (idx.0 - d_x, idx.1 - d_y)])
+            .filter(|other| self.is_site(*other))
+            .collect()
     }
 
     fn all_idxs(&self) -> Vec<Self::Index> {
@@ -128,6 +211,11 @@ impl<T: Copy + RandAtom, const W: usize, const H: usize> Lattice for Array2d<T,
         unsafe { std::slice::from_raw_parts_mut(self.grid.as_mut_ptr().cast(), W * H) }
     }
 
+    fn flat_index(&self, idx: Self::Index) -> usize {
+        let (x, y) = self.reduce_index(idx);
+        y as usize * W + x as usize
+    }
+
     fn random_idx(&self, rng: &mut MyRng) -> Self::Index {
         (rng.gen_range(0..W as isize), rng.gen_range(0..H as isize))
     }
@@ -139,11 +227,41 @@ impl<T: Copy + RandAtom, const W: usize, const H: usize> Lattice for Array2d<T,
         (x, y)
     }
 
+    fn translate(&self, idx: Self::Index, by: Self::Index) -> Option<Self::Index> {
+        let other = (idx.0 + by.0, idx.1 + by.1);
+        self.is_site(other).then(|| self.reduce_index(other))
+    }
+
     fn tot_sites(&self) -> usize {
         W * H
     }
 }
 
+impl<T: Copy + RandAtom, const W: usize, const H: usize> Geometry for Array2d<T, W, H> {
+    fn coordinates(&self, idx: Self::Index) -> [f32; 3] {
+        let (x, y) = self.reduce_index(idx);
+        [x as f32, y as f32, 0.0]
+    }
+
+    fn axes(&self) -> [[f32; 3]; 3] {
+        [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+    }
+
+    fn periods(&self) -> [Option<f32>; 3] {
+        [
+            self.boundaries[0].is_periodic().then_some(W as f32),
+            self.boundaries[1].is_periodic().then_some(H as f32),
+            None,
+        ]
+    }
+}
+
+impl<T: Copy + RandAtom, const W: usize, const H: usize> Grid for Array2d<T, W, H> {
+    fn grid_shape(&self) -> Vec<usize> {
+        vec![H, W]
+    }
+}
+
 impl<const W: usize, const H: usize> GifFrame for Array2d<BinAtom, W, H> {
     fn get_frame(&self) -> gif::Frame<'_> {
         gif::Frame::from_indexed_pixels(
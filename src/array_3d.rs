@@ -6,12 +6,28 @@ use std::{
 use itertools::Itertools;
 use rand::Rng;
 
-use crate::{BinAtom, GifFrame, Lattice, MyRng, RandAtom};
+use crate::{BinAtom, Boundary, Geometry, GifFrame, Grid, Lattice, MyRng, NeighborList, RandAtom};
+
+/// For every neighbor shell beyond the nearest neighbors one offset of each pair (o, -o).
+pub(crate) const SHELL_OFFSETS: [&[(isize, isize, isize)]; 2] = [
+    &[
+        (1, 1, 0),
+        (1, -1, 0),
+        (1, 0, 1),
+        (1, 0, -1),
+        (0, 1, 1),
+        (0, 1, -1),
+    ],
+    &[(1, 1, 1), (1, 1, -1), (1, -1, 1), (1, -1, -1)],
+];
 
 /// A 3D grid type that is Copy and allows indexes to "wrap around"
+/// The boundaries along x, y and z can be changed with the shape of the lattice,
+/// along axes which aren't periodic the indexes must not leave the grid.
 #[derive(Clone)]
 pub struct Array3d<T, const W: usize, const H: usize, const D: usize> {
     pub grid: Box<[[[T; W]; H]; D]>,
+    boundaries: [Boundary<T>; 3],
 }
 
 impl<T, const W: usize, const H: usize, const D: usize> Array3d<T, W, H, D>
@@ -24,8 +40,16 @@ where
     {
         Self {
             grid: Box::new([[[T::default(); W]; H]; D]),
+            boundaries: [Boundary::Periodic; 3],
         }
     }
+
+    /// This function returns false for indexes which leave the grid along a non periodic axis.
+    fn is_site(&self, idx: (isize, isize, isize)) -> bool {
+        (self.boundaries[0].is_periodic() || (0..W as isize).contains(&idx.0))
+            && (self.boundaries[1].is_periodic() || (0..H as isize).contains(&idx.1))
+            && (self.boundaries[2].is_periodic() || (0..D as isize).contains(&idx.2))
+    }
 }
 
 impl<T, const W: usize, const H: usize, const D: usize> Index<(isize, isize, isize)>
@@ -79,16 +103,20 @@ impl<T: Copy + RandAtom, const W: usize, const H: usize, const D: usize> Lattice
 {
     type Atom = T;
     type Index = (isize, isize, isize);
-    type Neighbors = [Self::Index; 6];
+    type Neighbors = NeighborList<Self::Index, 6>;
+    /// the boundaries along x, y and z
+    type Shape = [Boundary<T>; 3];
 
-    fn fill_value(val: Self::Atom) -> Self {
+    fn fill_value(shape: Self::Shape, val: Self::Atom) -> Self {
         Self {
             grid: Box::new([[[val; W]; H]; D]),
+            boundaries: shape,
         }
     }
 
-    fn fill_with_fn(func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self {
+    fn fill_with_fn(shape: Self::Shape, func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self {
         let mut out = Self::new();
+        out.boundaries = shape;
         for x in 0..W as isize {
             for y in 0..H as isize {
                 for z in 0..D as isize {
@@ -99,20 +127,27 @@ impl<T: Copy + RandAtom, const W: usize, const H: usize, const D: usize> Lattice
         out
     }
 
+    fn shape(&self) -> Self::Shape {
+        self.boundaries
+    }
+
     fn all_neighbors(&self) -> HashMap<(Self::Atom, Self::Atom), u32> {
         let mut out = HashMap::new();
         for x in 0..W as isize {
             for y in 0..H as isize {
                 for z in 0..D as isize {
-                    out.entry((self[(x, y, z)], self[(x - 1, y, z)]))
-                        .and_modify(|e| *e += 1)
-                        .or_insert(1);
-                    out.entry((self[(x, y, z)], self[(x, y - 1, z)]))
-                        .and_modify(|e| *e += 1)
-                        .or_insert(1);
-                    out.entry((self[(x, y, z)], self[(x, y, z - 1)]))
-                        .and_modify(|e| *e += 1)
-                        .or_insert(1);
+                    for other in [(x - 1, y, z), (x, y - 1, z), (x, y, z - 1)] {
+                        if self.is_site(other) {
+                            out.entry((self[(x, y, z)], self[other]))
+                                .and_modify(|e| *e += 1)
+                                .or_insert(1);
+                        }
+                    }
+                    for wall in self.wall_neighbors_to((x, y, z)) {
+                        out.entry((self[(x, y, z)], wall))
+                            .and_modify(|e| *e += 1)
+                            .or_insert(1);
+                    }
                 }
             }
         }
@@ -120,14 +155,73 @@ impl<T: Copy + RandAtom, const W: usize, const H: usize, const D: usize> Lattice
     }
 
     fn all_neighbors_to(&self, idx: Self::Index) -> Self::Neighbors {
-        [
+        let mut out = NeighborList::new(idx);
+        for other in [
             (idx.0 + 1, idx.1, idx.2),
             (idx.0 - 1, idx.1, idx.2),
             (idx.0, idx.1 + 1, idx.2),
             (idx.0, idx.1 - 1, idx.2),
             (idx.0, idx.1, idx.2 + 1),
             (idx.0, idx.1, idx.2 - 1),
-        ]
+        ] {
+            if self.is_site(other) {
+                out.push(other)
+            }
+        }
+        out
+    }
+
+    fn wall_neighbors_to(&self, idx: Self::Index) -> Vec<Self::Atom> {
+        let mut out = Vec::new();
+        for (i, (coord, len)) in [(idx.0, W), (idx.1, H), (idx.2, D)].into_iter().enumerate() {
+            if let Boundary::Wall(wall) = self.boundaries[i] {
+                if coord == 0 {
+                    out.push(wall)
+                }
+                if coord == len as isize - 1 {
+                    out.push(wall)
+                }
+            }
+        }
+        out
+    }
+
+    fn shells(&self) -> usize {
+        SHELL_OFFSETS.len() + 1
+    }
+
+    fn all_shell_neighbors(&self, shell: usize) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        if shell == 0 {
+            return self.all_neighbors();
+        }
+        let mut out = HashMap::new();
+        for x in 0..W as isize {
+            for y in 0..H as isize {
+                for z in 0..D as isize {
+                    for (d_x, d_y, d_z) in SHELL_OFFSETS[shell - 1] {
+                        let other = (x - d_x, y - d_y, z - d_z);
+                        if self.is_site(other) {
+                            out.entry((self[(x, y, z)], self[other]))
+                                .and_modify(|e| *e += 1)
+                                .or_insert(1);
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn shell_neighbors_to(&self, idx: Self::Index, shell: usize) -> Vec<Self::Index> {
+        if shell == 0 {
+            return self.all_neighbors_to(idx).as_ref().to_vec();
+        }
+        let (x, y, z) = idx;
+        SHELL_OFFSETS[shell - 1]
+            .iter()
+            .flat_map(|(d_x, d_y, d_z)| [(x + d_x, y + d_y, z + d_z), (x - d_x, y - d_y, z - d_z)])
+            .filter(|other| self.is_site(*other))
+            .collect()
     }
 
     fn all_idxs(&self) -> Vec<Self::Index> {
@@ -150,6 +244,11 @@ impl<T: Copy + RandAtom, const W: usize, const H: usize, const D: usize> Lattice
         unsafe { std::slice::from_raw_parts_mut(self.grid.as_mut_ptr().cast(), W * H * D) }
     }
 
+    fn flat_index(&self, idx: Self::Index) -> usize {
+        let (x, y, z) = self.reduce_index(idx);
+        (z as usize * H + y as usize) * W + x as usize
+    }
+
     fn random_idx(&self, rng: &mut MyRng) -> Self::Index {
         (
             rng.gen_range(0..W as isize),
@@ -166,11 +265,45 @@ impl<T: Copy + RandAtom, const W: usize, const H: usize, const D: usize> Lattice
         (x, y, z)
     }
 
+    fn translate(&self, idx: Self::Index, by: Self::Index) -> Option<Self::Index> {
+        let other = (idx.0 + by.0, idx.1 + by.1, idx.2 + by.2);
+        self.is_site(other).then(|| self.reduce_index(other))
+    }
+
     fn tot_sites(&self) -> usize {
         W * H * D
     }
 }
 
+impl<T: Copy + RandAtom, const W: usize, const H: usize, const D: usize> Geometry
+    for Array3d<T, W, H, D>
+{
+    fn coordinates(&self, idx: Self::Index) -> [f32; 3] {
+        let (x, y, z) = self.reduce_index(idx);
+        [x as f32, y as f32, z as f32]
+    }
+
+    fn axes(&self) -> [[f32; 3]; 3] {
+        [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+    }
+
+    fn periods(&self) -> [Option<f32>; 3] {
+        [
+            self.boundaries[0].is_periodic().then_some(W as f32),
+            self.boundaries[1].is_periodic().then_some(H as f32),
+            self.boundaries[2].is_periodic().then_some(D as f32),
+        ]
+    }
+}
+
+impl<T: Copy + RandAtom, const W: usize, const H: usize, const D: usize> Grid
+    for Array3d<T, W, H, D>
+{
+    fn grid_shape(&self) -> Vec<usize> {
+        vec![D, H, W]
+    }
+}
+
 impl<const W: usize, const H: usize, const D: usize> GifFrame for Array3d<BinAtom, W, H, D> {
     fn get_frame(&self) -> gif::Frame<'_> {
         let ptr = self.grid.as_ptr().cast();
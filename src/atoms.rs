@@ -1,10 +1,13 @@
 use crate::MyRng;
 use rand::Rng;
-use std::{hash::Hash, ops::Deref, usize};
+use std::{hash::Hash, ops::Deref};
 
 pub trait RandAtom: Default + Eq + PartialEq + Hash + Deref<Target = u8> {
     type Concentration: Copy;
     fn vacancy() -> Self;
+    fn is_vacancy(&self) -> bool {
+        *self == Self::vacancy()
+    }
     fn with_concentration(rng: &mut MyRng, cs: Self::Concentration) -> Self;
     fn all_atoms() -> Vec<Self>;
 }
@@ -21,15 +24,31 @@ pub trait Mark: RandAtom {
 #[repr(transparent)]
 pub struct BinAtom(u8);
 #[derive(Clone, Copy)]
-pub struct BinConcentration(f64);
+pub struct BinConcentration {
+    c_a: f64,
+    c_v: f64,
+}
 
 impl BinConcentration {
     pub fn new(c_a: f64, c_b: f64) -> Self {
-        Self(c_a / (c_a + c_b))
+        Self {
+            c_a: c_a / (c_a + c_b),
+            c_v: 0.0,
+        }
+    }
+
+    /// sets the fraction of sites which are vacancies
+    pub fn with_vacancies(self, c_v: f64) -> Self {
+        assert!((0.0..1.0).contains(&c_v));
+        Self { c_v, ..self }
     }
 
     pub fn get_c_a(&self) -> f64 {
-        self.0
+        self.c_a
+    }
+
+    pub fn get_c_v(&self) -> f64 {
+        self.c_v
     }
 }
 impl BinAtom {
@@ -55,7 +74,9 @@ impl RandAtom for BinAtom {
     }
 
     fn with_concentration(rng: &mut MyRng, cs: Self::Concentration) -> Self {
-        if rng.gen_bool(cs.0) {
+        if cs.c_v > 0.0 && rng.gen_bool(cs.c_v) {
+            Self::vacancy()
+        } else if rng.gen_bool(cs.c_a) {
             Self(0b0000)
         } else {
             Self(0b0001)
@@ -80,16 +101,134 @@ impl Mark for BinAtom {
     }
 }
 
+/// An atom of an alloy with `N` species.
+/// The species are numbered from `0` to `N - 1`.
+#[derive(Hash, PartialEq, Eq, Default, Clone, Copy)]
+#[repr(transparent)]
+pub struct MultiAtom<const N: usize>(u8);
+#[derive(Clone, Copy)]
+pub struct MultiConcentration<const N: usize> {
+    cs: [f64; N],
+    c_v: f64,
+}
+
+impl<const N: usize> MultiConcentration<N> {
+    /// the concentrations get normalized so they don't need to add up to 1
+    pub fn new(cs: [f64; N]) -> Self {
+        let tot: f64 = cs.iter().sum();
+        assert!(tot > 0.0 && cs.iter().all(|c| *c >= 0.0));
+        Self {
+            cs: cs.map(|c| c / tot),
+            c_v: 0.0,
+        }
+    }
+
+    /// sets the fraction of sites which are vacancies
+    pub fn with_vacancies(self, c_v: f64) -> Self {
+        assert!((0.0..1.0).contains(&c_v));
+        Self { c_v, ..self }
+    }
+
+    pub fn get_c(&self, species: usize) -> f64 {
+        self.cs[species]
+    }
+
+    pub fn get_c_v(&self) -> f64 {
+        self.c_v
+    }
+
+    pub fn as_array(&self) -> [f64; N] {
+        self.cs
+    }
+}
+
+impl<const N: usize> MultiAtom<N> {
+    // the highest bit is used by Mark and the bit below by the vacancy
+    const MAX_SPECIES: usize = 0b0100_0000;
+
+    pub fn new(num: u8) -> Self {
+        assert!(N <= Self::MAX_SPECIES);
+        assert!((num as usize) < N);
+        Self(num)
+    }
+}
+
+impl<const N: usize> Deref for MultiAtom<N> {
+    type Target = u8;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const N: usize> RandAtom for MultiAtom<N> {
+    type Concentration = MultiConcentration<N>;
+
+    fn vacancy() -> Self {
+        Self(0b0100_0000)
+    }
+
+    fn with_concentration(rng: &mut MyRng, cs: Self::Concentration) -> Self {
+        assert!(N <= Self::MAX_SPECIES);
+        if cs.c_v > 0.0 && rng.gen_bool(cs.c_v) {
+            return Self::vacancy();
+        }
+        let mut r = rng.gen::<f64>();
+        for (i, c) in cs.cs.iter().enumerate() {
+            if r < *c {
+                return Self(i as u8);
+            }
+            r -= c;
+        }
+        // only reachable by rounding errors
+        Self(N as u8 - 1)
+    }
+
+    fn all_atoms() -> Vec<Self> {
+        (0..N as u8).map(Self::new).collect()
+    }
+}
+
+impl<const N: usize> Mark for MultiAtom<N> {
+    unsafe fn mark(&mut self) {
+        self.0 |= 0b1000_0000
+    }
+
+    fn unmark(&mut self) {
+        self.0 &= 0b0111_1111
+    }
+
+    fn is_marked(&self) -> bool {
+        (self.0 & 0b1000_0000) != 0
+    }
+}
+
 pub trait Energies<A: RandAtom> {
+    /// the interaction energy between nearest neighbors
     fn get_interaction_energy(&self, a_1: A, a_2: A) -> f32;
     fn as_dict(&self) -> String;
+
+    /// The number of neighbor shells with an interaction, see `Lattice::shells`.
+    fn shells(&self) -> usize {
+        1
+    }
+    /// the interaction energy between neighbors in `shell`
+    fn get_shell_interaction_energy(&self, shell: usize, a_1: A, a_2: A) -> f32 {
+        if shell == 0 {
+            self.get_interaction_energy(a_1, a_2)
+        } else {
+            0.0
+        }
+    }
 }
 
 impl Energies<BinAtom> for [f32; 4] {
     fn get_interaction_energy(&self, a_1: BinAtom, a_2: BinAtom) -> f32 {
+        // vacancies have no interaction, use VacancyEnergies to change this
+        if a_1.is_vacancy() || a_2.is_vacancy() {
+            return 0.0;
+        }
         // Safety this is save as with the last & the index is ensured to be < 4
-        // this is also correct with the vacancy value of 0b0000_0100 but it depends on the
-        // concrete implementation of the move vacancy method
         unsafe { *self.get_unchecked((((*a_1 << 1) + *a_2) & 0b0000_0011) as usize) }
     }
 
@@ -100,3 +239,203 @@ impl Energies<BinAtom> for [f32; 4] {
         )
     }
 }
+
+/// Pair interaction energies between `N` species.
+/// The entry `[i][j]` is the bond energy between species `i` and `j`,
+/// thus the matrix has to be symmetric.
+#[derive(Clone, Copy, Debug)]
+pub struct PairEnergies<const N: usize>([[f32; N]; N]);
+
+impl<const N: usize> PairEnergies<N> {
+    pub fn new(matrix: [[f32; N]; N]) -> Self {
+        assert!(
+            Self::is_symmetric(&matrix),
+            "pair energies have to be symmetric"
+        );
+        Self(matrix)
+    }
+
+    pub fn is_symmetric(matrix: &[[f32; N]; N]) -> bool {
+        (0..N).all(|i| (0..i).all(|j| matrix[i][j] == matrix[j][i]))
+    }
+
+    pub fn as_matrix(&self) -> [[f32; N]; N] {
+        self.0
+    }
+
+    fn get(&self, a_1: u8, a_2: u8) -> f32 {
+        // vacancies are not part of the matrix and have no interaction,
+        // use VacancyEnergies to change this
+        let row = self.0.get(a_1 as usize);
+        match row.and_then(|row| row.get(a_2 as usize)) {
+            Some(energy) => *energy,
+            None => 0.0,
+        }
+    }
+}
+
+impl<const N: usize> Energies<MultiAtom<N>> for PairEnergies<N> {
+    fn get_interaction_energy(&self, a_1: MultiAtom<N>, a_2: MultiAtom<N>) -> f32 {
+        self.get(*a_1, *a_2)
+    }
+
+    fn as_dict(&self) -> String {
+        let pairs: Vec<String> = (0..N)
+            .flat_map(|i| (i..N).map(move |j| (i, j)))
+            .map(|(i, j)| format!("({}, {}): {}", i, j, self.0[i][j]))
+            .collect();
+        format!("{{{}}}", pairs.join(", "))
+    }
+}
+
+impl Energies<BinAtom> for PairEnergies<2> {
+    fn get_interaction_energy(&self, a_1: BinAtom, a_2: BinAtom) -> f32 {
+        self.get(*a_1, *a_2)
+    }
+
+    fn as_dict(&self) -> String {
+        format!(
+            "{{(0, 0): {}, (0, 1): {}, (1, 1): {}}}",
+            self.0[0][0], self.0[0][1], self.0[1][1]
+        )
+    }
+}
+
+/// Adds bond energies between vacancies and atoms to any other `Energies`.
+/// The entry `vacancy_bonds[i]` is the bond energy between a vacancy and species `i`,
+/// `N` is the number of species.
+#[derive(Clone, Debug)]
+pub struct VacancyEnergies<E, const N: usize> {
+    energies: E,
+    vacancy_bonds: [f32; N],
+    vacancy_vacancy: f32,
+}
+
+impl<E, const N: usize> VacancyEnergies<E, N> {
+    pub fn new(energies: E, vacancy_bonds: [f32; N], vacancy_vacancy: f32) -> Self {
+        Self {
+            energies,
+            vacancy_bonds,
+            vacancy_vacancy,
+        }
+    }
+
+    pub fn atom_energies(&self) -> &E {
+        &self.energies
+    }
+
+    fn get<A: RandAtom>(&self, a_1: A, a_2: A) -> f32
+    where
+        E: Energies<A>,
+    {
+        match (a_1.is_vacancy(), a_2.is_vacancy()) {
+            (false, false) => self.energies.get_interaction_energy(a_1, a_2),
+            (true, false) => self.vacancy_bonds[*a_2 as usize],
+            (false, true) => self.vacancy_bonds[*a_1 as usize],
+            (true, true) => self.vacancy_vacancy,
+        }
+    }
+
+    /// the vacancy bonds only apply to the nearest neighbors, the other shells are
+    /// taken from the wrapped energies
+    fn get_shell<A: RandAtom>(&self, shell: usize, a_1: A, a_2: A) -> f32
+    where
+        E: Energies<A>,
+    {
+        if shell == 0 {
+            self.get(a_1, a_2)
+        } else {
+            self.energies.get_shell_interaction_energy(shell, a_1, a_2)
+        }
+    }
+
+    fn dict<A: RandAtom>(&self) -> String
+    where
+        E: Energies<A>,
+    {
+        let mut dict = self.energies.as_dict();
+        dict.pop();
+        for (i, energy) in self.vacancy_bonds.iter().enumerate() {
+            dict.push_str(&format!(", ('v', {}): {}", i, energy));
+        }
+        dict.push_str(&format!(", ('v', 'v'): {}}}", self.vacancy_vacancy));
+        dict
+    }
+}
+
+impl<E: Energies<BinAtom>> Energies<BinAtom> for VacancyEnergies<E, 2> {
+    fn get_interaction_energy(&self, a_1: BinAtom, a_2: BinAtom) -> f32 {
+        self.get(a_1, a_2)
+    }
+
+    fn shells(&self) -> usize {
+        self.energies.shells()
+    }
+
+    fn get_shell_interaction_energy(&self, shell: usize, a_1: BinAtom, a_2: BinAtom) -> f32 {
+        self.get_shell(shell, a_1, a_2)
+    }
+
+    fn as_dict(&self) -> String {
+        self.dict::<BinAtom>()
+    }
+}
+
+impl<E: Energies<MultiAtom<N>>, const N: usize> Energies<MultiAtom<N>> for VacancyEnergies<E, N> {
+    fn get_interaction_energy(&self, a_1: MultiAtom<N>, a_2: MultiAtom<N>) -> f32 {
+        self.get(a_1, a_2)
+    }
+
+    fn shells(&self) -> usize {
+        self.energies.shells()
+    }
+
+    fn get_shell_interaction_energy(
+        &self,
+        shell: usize,
+        a_1: MultiAtom<N>,
+        a_2: MultiAtom<N>,
+    ) -> f32 {
+        self.get_shell(shell, a_1, a_2)
+    }
+
+    fn as_dict(&self) -> String {
+        self.dict::<MultiAtom<N>>()
+    }
+}
+
+/// Energies for interactions beyond the nearest neighbors.
+/// The energies at position `i` are used for the neighbors in shell `i`.
+#[derive(Clone, Debug)]
+pub struct ShellEnergies<E>(Vec<E>);
+
+impl<E> ShellEnergies<E> {
+    pub fn new(shells: Vec<E>) -> Self {
+        assert!(!shells.is_empty(), "there has to be at least one shell");
+        Self(shells)
+    }
+}
+
+impl<A: RandAtom, E: Energies<A>> Energies<A> for ShellEnergies<E> {
+    fn get_interaction_energy(&self, a_1: A, a_2: A) -> f32 {
+        self.0[0].get_interaction_energy(a_1, a_2)
+    }
+
+    fn as_dict(&self) -> String {
+        let shells: Vec<String> = self
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, energies)| format!("{}: {}", i, energies.as_dict()))
+            .collect();
+        format!("{{{}}}", shells.join(", "))
+    }
+
+    fn shells(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get_shell_interaction_energy(&self, shell: usize, a_1: A, a_2: A) -> f32 {
+        self.0[shell].get_interaction_energy(a_1, a_2)
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+use itertools::Itertools;
+use rand::Rng;
+
+use crate::{Geometry, Lattice, MyRng, RandAtom};
+
+/// A face centered cubic lattice with W * H * D conventional cells,
+/// every site has 12 nearest neighbors.
+pub type Fcc<T, const W: usize, const H: usize, const D: usize> =
+    Cubic<T, FaceCentered, 4, 12, W, H, D>;
+
+/// A body centered cubic lattice with W * H * D conventional cells,
+/// every site has 8 nearest neighbors.
+pub type Bcc<T, const W: usize, const H: usize, const D: usize> =
+    Cubic<T, BodyCentered, 2, 8, W, H, D>;
+
+/// Describes the basis of a cubic lattice.
+/// All positions and offsets are in units of half the lattice constant.
+pub trait CubicBasis {
+    /// the positions of the sites in the conventional cell
+    const BASIS: &'static [(isize, isize, isize)];
+    /// For every neighbor shell one offset of each pair (o, -o).
+    const SHELLS: &'static [&'static [(isize, isize, isize)]];
+}
+
+#[derive(Clone, Copy)]
+pub struct FaceCentered;
+
+impl CubicBasis for FaceCentered {
+    const BASIS: &'static [(isize, isize, isize)] = &[(0, 0, 0), (1, 1, 0), (1, 0, 1), (0, 1, 1)];
+    const SHELLS: &'static [&'static [(isize, isize, isize)]] = &[
+        &[
+            (1, 1, 0),
+            (1, -1, 0),
+            (1, 0, 1),
+            (1, 0, -1),
+            (0, 1, 1),
+            (0, 1, -1),
+        ],
+        &[(2, 0, 0), (0, 2, 0), (0, 0, 2)],
+    ];
+}
+
+#[derive(Clone, Copy)]
+pub struct BodyCentered;
+
+impl CubicBasis for BodyCentered {
+    const BASIS: &'static [(isize, isize, isize)] = &[(0, 0, 0), (1, 1, 1)];
+    const SHELLS: &'static [&'static [(isize, isize, isize)]] = &[
+        &[(1, 1, 1), (1, 1, -1), (1, -1, 1), (1, -1, -1)],
+        &[(2, 0, 0), (0, 2, 0), (0, 0, 2)],
+    ];
+}
+
+/// A cubic lattice with a basis of B sites per conventional cell.
+/// The sites are indexed with (x, y, z, b) where (x, y, z) is the conventional cell
+/// and b the site in the cell. The cells "wrap around" like in `Array3d`.
+/// NN is the number of nearest neighbors, use the aliases `Fcc` and `Bcc`.
+#[derive(Clone)]
+pub struct Cubic<
+    T,
+    K,
+    const B: usize,
+    const NN: usize,
+    const W: usize,
+    const H: usize,
+    const D: usize,
+> {
+    grid: Box<[[[[T; B]; W]; H]; D]>,
+    kind: PhantomData<K>,
+}
+
+impl<T, K, const B: usize, const NN: usize, const W: usize, const H: usize, const D: usize>
+    Cubic<T, K, B, NN, W, H, D>
+where
+    T: Copy,
+    K: CubicBasis,
+{
+    pub fn new() -> Self
+    where
+        T: Default,
+    {
+        Self::fill(T::default())
+    }
+
+    fn fill(val: T) -> Self {
+        assert_eq!(K::BASIS.len(), B);
+        assert_eq!(K::SHELLS[0].len() * 2, NN);
+        Self {
+            grid: Box::new([[[[val; B]; W]; H]; D]),
+            kind: PhantomData,
+        }
+    }
+
+    /// This function returns the index of the site at `offset` from `idx`.
+    fn offset(
+        idx: (isize, isize, isize, usize),
+        offset: (isize, isize, isize),
+    ) -> (isize, isize, isize, usize) {
+        let (b_x, b_y, b_z) = K::BASIS[idx.3];
+        let x = 2 * idx.0 + b_x + offset.0;
+        let y = 2 * idx.1 + b_y + offset.1;
+        let z = 2 * idx.2 + b_z + offset.2;
+        let in_cell = (x.rem_euclid(2), y.rem_euclid(2), z.rem_euclid(2));
+        let b = K::BASIS
+            .iter()
+            .position(|pos| *pos == in_cell)
+            .expect("offsets lead to lattice sites");
+        (x.div_euclid(2), y.div_euclid(2), z.div_euclid(2), b)
+    }
+}
+
+impl<T, K, const B: usize, const NN: usize, const W: usize, const H: usize, const D: usize>
+    Index<(isize, isize, isize, usize)> for Cubic<T, K, B, NN, W, H, D>
+{
+    type Output = T;
+
+    fn index(&self, index: (isize, isize, isize, usize)) -> &Self::Output {
+        let (x, y, z, b) = index;
+        let x = x.rem_euclid(W as isize);
+        let y = y.rem_euclid(H as isize);
+        let z = z.rem_euclid(D as isize);
+        // Safety this is safe because of the above rem
+        unsafe {
+            &(*self.grid)
+                .get_unchecked(z as usize)
+                .get_unchecked(y as usize)
+                .get_unchecked(x as usize)[b]
+        }
+    }
+}
+
+impl<T, K, const B: usize, const NN: usize, const W: usize, const H: usize, const D: usize>
+    IndexMut<(isize, isize, isize, usize)> for Cubic<T, K, B, NN, W, H, D>
+{
+    fn index_mut(&mut self, index: (isize, isize, isize, usize)) -> &mut Self::Output {
+        let (x, y, z, b) = index;
+        let x = x.rem_euclid(W as isize);
+        let y = y.rem_euclid(H as isize);
+        let z = z.rem_euclid(D as isize);
+        // Safety this is safe because of the above rem
+        unsafe {
+            &mut (*self.grid)
+                .get_unchecked_mut(z as usize)
+                .get_unchecked_mut(y as usize)
+                .get_unchecked_mut(x as usize)[b]
+        }
+    }
+}
+
+impl<T, K, const B: usize, const NN: usize, const W: usize, const H: usize, const D: usize> Default
+    for Cubic<T, K, B, NN, W, H, D>
+where
+    T: Copy + Default,
+    K: CubicBasis,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, K, const B: usize, const NN: usize, const W: usize, const H: usize, const D: usize> Lattice
+    for Cubic<T, K, B, NN, W, H, D>
+where
+    T: Copy + RandAtom,
+    K: CubicBasis,
+{
+    type Atom = T;
+    type Index = (isize, isize, isize, usize);
+    type Neighbors = [Self::Index; NN];
+    type Shape = ();
+
+    fn fill_value(_shape: Self::Shape, val: Self::Atom) -> Self {
+        Self::fill(val)
+    }
+
+    fn fill_with_fn(_shape: Self::Shape, func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self {
+        let mut out = Self::new();
+        for idx in out.all_idxs() {
+            out[idx] = func(idx);
+        }
+        out
+    }
+
+    fn shape(&self) -> Self::Shape {}
+
+    fn all_neighbors(&self) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        self.all_shell_neighbors(0)
+    }
+
+    fn all_neighbors_to(&self, idx: Self::Index) -> Self::Neighbors {
+        let half = K::SHELLS[0];
+        std::array::from_fn(|i| {
+            let (d_x, d_y, d_z) = half[i / 2];
+            if i % 2 == 0 {
+                Self::offset(idx, (d_x, d_y, d_z))
+            } else {
+                Self::offset(idx, (-d_x, -d_y, -d_z))
+            }
+        })
+    }
+
+    fn shells(&self) -> usize {
+        K::SHELLS.len()
+    }
+
+    fn all_shell_neighbors(&self, shell: usize) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        let mut out = HashMap::new();
+        for idx in self.all_idxs() {
+            for (d_x, d_y, d_z) in K::SHELLS[shell] {
+                out.entry((self[idx], self[Self::offset(idx, (-d_x, -d_y, -d_z))]))
+                    .and_modify(|e| *e += 1)
+                    .or_insert(1);
+            }
+        }
+        out
+    }
+
+    fn shell_neighbors_to(&self, idx: Self::Index, shell: usize) -> Vec<Self::Index> {
+        K::SHELLS[shell]
+            .iter()
+            .flat_map(|(d_x, d_y, d_z)| {
+                [
+                    Self::offset(idx, (*d_x, *d_y, *d_z)),
+                    Self::offset(idx, (-d_x, -d_y, -d_z)),
+                ]
+            })
+            .collect()
+    }
+
+    fn all_idxs(&self) -> Vec<Self::Index> {
+        (0..D as isize)
+            .cartesian_product(0..H as isize)
+            .cartesian_product(0..W as isize)
+            .cartesian_product(0..B)
+            .map(|(((z, y), x), b)| (x, y, z, b))
+            .collect_vec()
+    }
+
+    fn tot_sites(&self) -> usize {
+        W * H * D * B
+    }
+
+    fn as_flat_slice(&self) -> &[Self::Atom] {
+        // Safety: the memory layout of [[T; N]; N] is the same as [T]
+        // TODO zero sized types
+        unsafe { std::slice::from_raw_parts(self.grid.as_ptr().cast(), W * H * D * B) }
+    }
+
+    fn as_flat_slice_mut(&mut self) -> &mut [Self::Atom] {
+        // Safety: the memory layout of [[T; N]; N] is the same as [T]
+        // TODO zero sized types
+        unsafe { std::slice::from_raw_parts_mut(self.grid.as_mut_ptr().cast(), W * H * D * B) }
+    }
+
+    fn flat_index(&self, idx: Self::Index) -> usize {
+        let (x, y, z, b) = self.reduce_index(idx);
+        ((z as usize * H + y as usize) * W + x as usize) * B + b
+    }
+
+    fn random_idx(&self, rng: &mut MyRng) -> Self::Index {
+        (
+            rng.gen_range(0..W as isize),
+            rng.gen_range(0..H as isize),
+            rng.gen_range(0..D as isize),
+            rng.gen_range(0..B),
+        )
+    }
+
+    fn reduce_index(&self, idx: Self::Index) -> Self::Index {
+        let (x, y, z, b) = idx;
+        let x = x.rem_euclid(W as isize);
+        let y = y.rem_euclid(H as isize);
+        let z = z.rem_euclid(D as isize);
+        (x, y, z, b)
+    }
+
+    /// `by` is the displacement from the site (0, 0, 0, 0) to the site `by`,
+    /// so the offset -o is the index of the site at the negated displacement
+    fn translate(&self, idx: Self::Index, by: Self::Index) -> Option<Self::Index> {
+        let (b_x, b_y, b_z) = K::BASIS[by.3];
+        let displacement = (2 * by.0 + b_x, 2 * by.1 + b_y, 2 * by.2 + b_z);
+        Some(self.reduce_index(Self::offset(idx, displacement)))
+    }
+}
+
+impl<T, K, const B: usize, const NN: usize, const W: usize, const H: usize, const D: usize> Geometry
+    for Cubic<T, K, B, NN, W, H, D>
+where
+    T: Copy + RandAtom,
+    K: CubicBasis,
+{
+    /// the coordinates are given in units of the edge of the cubic cell
+    fn coordinates(&self, idx: Self::Index) -> [f32; 3] {
+        let (x, y, z, b) = self.reduce_index(idx);
+        let (b_x, b_y, b_z) = K::BASIS[b];
+        [
+            x as f32 + b_x as f32 / 2.0,
+            y as f32 + b_y as f32 / 2.0,
+            z as f32 + b_z as f32 / 2.0,
+        ]
+    }
+
+    fn axes(&self) -> [[f32; 3]; 3] {
+        [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+    }
+
+    fn periods(&self) -> [Option<f32>; 3] {
+        [Some(W as f32), Some(H as f32), Some(D as f32)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BinAtom;
+
+    fn assert_translations_invert<K: CubicBasis, const B: usize, const NN: usize>() {
+        let lattice = Cubic::<BinAtom, K, B, NN, 4, 4, 4>::new();
+        for by in lattice.all_idxs() {
+            let (b_x, b_y, b_z) = K::BASIS[by.3];
+            let back = Cubic::<BinAtom, K, B, NN, 4, 4, 4>::offset(
+                (0, 0, 0, 0),
+                (-2 * by.0 - b_x, -2 * by.1 - b_y, -2 * by.2 - b_z),
+            );
+            for idx in lattice.all_idxs() {
+                let there = lattice.translate(idx, by).expect("the lattice is periodic");
+                assert_eq!(lattice.translate(there, back), Some(idx));
+            }
+        }
+    }
+
+    #[test]
+    fn fcc_translations_invert() {
+        assert_translations_invert::<FaceCentered, 4, 12>();
+    }
+
+    #[test]
+    fn bcc_translations_invert() {
+        assert_translations_invert::<BodyCentered, 2, 8>();
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::HashMap,
+    ops::{Index, IndexMut},
+};
+
+use itertools::Itertools;
+use rand::Rng;
+
+use crate::{array_2d::SHELL_OFFSETS, BinAtom, Geometry, GifFrame, Grid, Lattice, MyRng, RandAtom};
+
+/// The size of a `DynArray2d`.
+/// There is no default size, so systems of this lattice have to be created with
+/// `System::with_shape`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Size2d {
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Like `Array2d` but the width and height are chosen at runtime.
+/// The shape of this lattice is its `Size2d`.
+#[derive(Clone)]
+pub struct DynArray2d<T> {
+    grid: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T: Copy> DynArray2d<T> {
+    pub fn new(width: usize, height: usize) -> Self
+    where
+        T: Default,
+    {
+        Self::filled(width, height, T::default())
+    }
+
+    fn filled(width: usize, height: usize, val: T) -> Self {
+        assert!(width > 0 && height > 0, "the array can't be empty");
+        Self {
+            grid: vec![val; width * height],
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn flat_index(&self, index: (isize, isize)) -> usize {
+        let (x, y) = index;
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        y * self.width + x
+    }
+}
+
+impl<T: Copy> Index<(isize, isize)> for DynArray2d<T> {
+    type Output = T;
+
+    fn index(&self, index: (isize, isize)) -> &Self::Output {
+        let i = self.flat_index(index);
+        // Safety this is safe because of the rem in flat_index
+        unsafe { self.grid.get_unchecked(i) }
+    }
+}
+
+impl<T: Copy> IndexMut<(isize, isize)> for DynArray2d<T> {
+    fn index_mut(&mut self, index: (isize, isize)) -> &mut Self::Output {
+        let i = self.flat_index(index);
+        // Safety this is safe because of the rem in flat_index
+        unsafe { self.grid.get_unchecked_mut(i) }
+    }
+}
+
+impl<T: Copy + RandAtom> Lattice for DynArray2d<T> {
+    type Atom = T;
+    type Index = (isize, isize);
+    type Neighbors = [Self::Index; 4];
+    type Shape = Size2d;
+
+    fn fill_value(shape: Self::Shape, val: Self::Atom) -> Self {
+        Self::filled(shape.width, shape.height, val)
+    }
+
+    fn fill_with_fn(shape: Self::Shape, func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self {
+        let mut out = Self::new(shape.width, shape.height);
+        for x in 0..shape.width as isize {
+            for y in 0..shape.height as isize {
+                out[(x, y)] = func((x, y));
+            }
+        }
+        out
+    }
+
+    fn shape(&self) -> Self::Shape {
+        Size2d {
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    fn all_neighbors(&self) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        let mut out = HashMap::new();
+        for x in 0..self.width as isize {
+            for y in 0..self.height as isize {
+                out.entry((self[(x, y)], self[(x - 1, y)]))
+                    .and_modify(|e| *e += 1)
+                    .or_insert(1);
+                out.entry((self[(x, y)], self[(x, y - 1)]))
+                    .and_modify(|e| *e += 1)
+                    .or_insert(1);
+            }
+        }
+        out
+    }
+
+    fn all_neighbors_to(&self, idx: Self::Index) -> Self::Neighbors {
+        [
+            (idx.0 + 1, idx.1),
+            (idx.0 - 1, idx.1),
+            (idx.0, idx.1 + 1),
+            (idx.0, idx.1 - 1),
+        ]
+    }
+
+    fn shells(&self) -> usize {
+        SHELL_OFFSETS.len() + 1
+    }
+
+    fn all_shell_neighbors(&self, shell: usize) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        if shell == 0 {
+            return self.all_neighbors();
+        }
+        let mut out = HashMap::new();
+        for x in 0..self.width as isize {
+            for y in 0..self.height as isize {
+                for (d_x, d_y) in SHELL_OFFSETS[shell - 1] {
+                    out.entry((self[(x, y)], self[(x - d_x, y - d_y)]))
+                        .and_modify(|e| *e += 1)
+                        .or_insert(1);
+                }
+            }
+        }
+        out
+    }
+
+    fn shell_neighbors_to(&self, idx: Self::Index, shell: usize) -> Vec<Self::Index> {
+        if shell == 0 {
+            return self.all_neighbors_to(idx).to_vec();
+        }
+        SHELL_OFFSETS[shell - 1]
+            .iter()
+            .flat_map(|(d_x, d_y)| [(idx.0 + d_x, idx.1 + d_y), (idx.0 - d_x, idx.1 - d_y)])
+            .collect()
+    }
+
+    fn all_idxs(&self) -> Vec<Self::Index> {
+        (0..self.height as isize)
+            .cartesian_product(0..self.width as isize)
+            .map(|(y, x)| (x, y))
+            .collect_vec()
+    }
+
+    fn tot_sites(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn as_flat_slice(&self) -> &[Self::Atom] {
+        &self.grid
+    }
+
+    fn as_flat_slice_mut(&mut self) -> &mut [Self::Atom] {
+        &mut self.grid
+    }
+
+    fn flat_index(&self, idx: Self::Index) -> usize {
+        // calls the inherent function, which is also used for the indexing
+        DynArray2d::flat_index(self, idx)
+    }
+
+    fn random_idx(&self, rng: &mut MyRng) -> Self::Index {
+        (
+            rng.gen_range(0..self.width as isize),
+            rng.gen_range(0..self.height as isize),
+        )
+    }
+
+    fn reduce_index(&self, idx: Self::Index) -> Self::Index {
+        let (x, y) = idx;
+        let x = x.rem_euclid(self.width as isize);
+        let y = y.rem_euclid(self.height as isize);
+        (x, y)
+    }
+
+    fn translate(&self, idx: Self::Index, by: Self::Index) -> Option<Self::Index> {
+        Some(self.reduce_index((idx.0 + by.0, idx.1 + by.1)))
+    }
+}
+
+impl<T: Copy + RandAtom> Geometry for DynArray2d<T> {
+    fn coordinates(&self, idx: Self::Index) -> [f32; 3] {
+        let (x, y) = self.reduce_index(idx);
+        [x as f32, y as f32, 0.0]
+    }
+
+    fn axes(&self) -> [[f32; 3]; 3] {
+        [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+    }
+
+    fn periods(&self) -> [Option<f32>; 3] {
+        [Some(self.width as f32), Some(self.height as f32), None]
+    }
+}
+
+impl<T: Copy + RandAtom> Grid for DynArray2d<T> {
+    fn grid_shape(&self) -> Vec<usize> {
+        vec![self.height, self.width]
+    }
+}
+
+impl GifFrame for DynArray2d<BinAtom> {
+    fn get_frame(&self) -> gif::Frame<'_> {
+        gif::Frame::from_indexed_pixels(
+            self.width as u16,
+            self.height as u16,
+            // Safety: BinAtom is repr(transparent) over u8
+            unsafe { std::slice::from_raw_parts(self.grid.as_ptr().cast(), self.grid.len()) },
+            None,
+        )
+    }
+}
@@ -0,0 +1,86 @@
+/// The start of the equilibrated part of a time series, see `detect_equilibration`.
+#[derive(Debug, Clone, Copy)]
+pub struct Equilibration {
+    start: usize,
+    statistical_inefficiency: f64,
+    effective_samples: f64,
+}
+
+impl Equilibration {
+    /// the index of the first sample which is equilibrated
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// the statistical inefficiency of the samples from `start` on
+    pub fn statistical_inefficiency(&self) -> f64 {
+        self.statistical_inefficiency
+    }
+
+    /// the number of uncorrelated samples from `start` on
+    pub fn effective_samples(&self) -> f64 {
+        self.effective_samples
+    }
+}
+
+/// This function estimates the statistical inefficiency g = 1 + 2 * tau_int of a time series,
+/// which is the number of samples between two uncorrelated samples.
+/// The sum over the autocorrelation function stops at its first value <= 0,
+/// the lags grow by one more each time to keep this fast for long correlations.
+pub fn statistical_inefficiency(series: &[f32]) -> f64 {
+    let n = series.len();
+    if n < 2 {
+        return 1.0;
+    }
+    let mean = series.iter().map(|x| *x as f64).sum::<f64>() / n as f64;
+    let deltas: Vec<f64> = series.iter().map(|x| *x as f64 - mean).collect();
+    let variance = deltas.iter().map(|d| d * d).sum::<f64>() / n as f64;
+    if variance == 0.0 {
+        return 1.0;
+    }
+
+    let mut g = 1.0;
+    let mut t = 1;
+    let mut increment = 1;
+    while t < n - 1 {
+        let c = deltas[..n - t]
+            .iter()
+            .zip(&deltas[t..])
+            .map(|(a, b)| a * b)
+            .sum::<f64>()
+            / ((n - t) as f64 * variance);
+        // the first few lags are always used, as the autocorrelation can be noisy there
+        if c <= 0.0 && t > 3 {
+            break;
+        }
+        g += 2.0 * c * (1.0 - t as f64 / n as f64) * increment as f64;
+        t += increment;
+        increment += 1;
+    }
+    g.max(1.0)
+}
+
+/// This function detects the start of the equilibrated part of a time series with the method
+/// of Chodera (J. Chem. Theory Comput. 2016, 12, 1799).
+/// The start is the sample which maximizes the number of uncorrelated samples after it,
+/// every `stride`-th sample is tried.
+pub fn detect_equilibration(series: &[f32], stride: usize) -> Equilibration {
+    assert!(stride > 0);
+    let mut best = Equilibration {
+        start: 0,
+        statistical_inefficiency: 1.0,
+        effective_samples: 0.0,
+    };
+    for start in (0..series.len().saturating_sub(1)).step_by(stride) {
+        let g = statistical_inefficiency(&series[start..]);
+        let effective_samples = (series.len() - start) as f64 / g;
+        if effective_samples > best.effective_samples {
+            best = Equilibration {
+                start,
+                statistical_inefficiency: g,
+                effective_samples,
+            };
+        }
+    }
+    best
+}
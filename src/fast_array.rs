@@ -5,7 +5,7 @@ use std::{
 
 use rand::Rng;
 
-use crate::{BinAtom, GifFrame, Lattice, RandAtom};
+use crate::{array_2d::SHELL_OFFSETS, BinAtom, Geometry, GifFrame, Grid, Lattice, RandAtom};
 
 /// A modular 2d Array this implementation uses bit manipulation to implement the modularity of the grid.
 /// Because of this SIDE needs to be a power of two with POW beeing this power.
@@ -69,12 +69,13 @@ impl<T: Copy + RandAtom, const SIDE: usize, const POW: usize> Lattice for FastAr
     type Index = (usize, usize);
 
     type Neighbors = [Self::Index; 4];
+    type Shape = ();
 
-    fn fill_value(val: Self::Atom) -> Self {
+    fn fill_value(_shape: Self::Shape, val: Self::Atom) -> Self {
         Self(Box::new([[val; SIDE]; SIDE]))
     }
 
-    fn fill_with_fn(func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self {
+    fn fill_with_fn(_shape: Self::Shape, func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self {
         let mut new = Self::new();
         for y in 0..SIDE {
             for x in 0..SIDE {
@@ -85,6 +86,8 @@ impl<T: Copy + RandAtom, const SIDE: usize, const POW: usize> Lattice for FastAr
         new
     }
 
+    fn shape(&self) -> Self::Shape {}
+
     fn all_neighbors(&self) -> HashMap<(Self::Atom, Self::Atom), u32> {
         let mut out = HashMap::new();
         for y in 0..SIDE {
@@ -115,6 +118,49 @@ impl<T: Copy + RandAtom, const SIDE: usize, const POW: usize> Lattice for FastAr
         ]
     }
 
+    fn shells(&self) -> usize {
+        SHELL_OFFSETS.len() + 1
+    }
+
+    fn all_shell_neighbors(&self, shell: usize) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        if shell == 0 {
+            return self.all_neighbors();
+        }
+        let mut out = HashMap::new();
+        for y in 0..SIDE {
+            for x in 0..SIDE {
+                for (d_x, d_y) in SHELL_OFFSETS[shell - 1] {
+                    // Safety: by the above loop x and y are < SIDE
+                    unsafe {
+                        out.entry((
+                            *self.get_unchecked((x, y)),
+                            self[(x.wrapping_add_signed(*d_x), y.wrapping_add_signed(*d_y))],
+                        ))
+                        .and_modify(|e| *e += 1)
+                        .or_insert(1);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn shell_neighbors_to(&self, idx: Self::Index, shell: usize) -> Vec<Self::Index> {
+        if shell == 0 {
+            return self.all_neighbors_to(idx).to_vec();
+        }
+        let (x, y) = idx;
+        SHELL_OFFSETS[shell - 1]
+            .iter()
+            .flat_map(|(d_x, d_y)| {
+                [
+                    (x.wrapping_add_signed(*d_x), y.wrapping_add_signed(*d_y)),
+                    (x.wrapping_add_signed(-d_x), y.wrapping_add_signed(-d_y)),
+                ]
+            })
+            .collect()
+    }
+
     fn all_idxs(&self) -> Vec<Self::Index> {
         let mut out = Vec::with_capacity(SIDE * SIDE);
         for y in 0..SIDE {
@@ -141,6 +187,10 @@ impl<T: Copy + RandAtom, const SIDE: usize, const POW: usize> Lattice for FastAr
         unsafe { std::slice::from_raw_parts_mut(self.0.as_mut_ptr().cast(), SIDE * SIDE) }
     }
 
+    fn flat_index(&self, idx: Self::Index) -> usize {
+        (idx.1 & Self::MASK) * SIDE + (idx.0 & Self::MASK)
+    }
+
     fn random_idx(&self, rng: &mut crate::MyRng) -> Self::Index {
         (rng.gen_range(0..SIDE), rng.gen_range(0..SIDE))
     }
@@ -148,6 +198,38 @@ impl<T: Copy + RandAtom, const SIDE: usize, const POW: usize> Lattice for FastAr
     fn reduce_index(&self, idx: Self::Index) -> Self::Index {
         (idx.0 & Self::MASK, idx.0 & Self::MASK)
     }
+
+    /// the offsets wrap around, so a negative offset -d is given as `d.wrapping_neg()`
+    fn translate(&self, idx: Self::Index, by: Self::Index) -> Option<Self::Index> {
+        Some((
+            idx.0.wrapping_add(by.0) & Self::MASK,
+            idx.1.wrapping_add(by.1) & Self::MASK,
+        ))
+    }
+}
+
+impl<T: Copy + RandAtom, const SIDE: usize, const POW: usize> Geometry for FastArray<T, SIDE, POW> {
+    fn coordinates(&self, idx: Self::Index) -> [f32; 3] {
+        [
+            (idx.0 & Self::MASK) as f32,
+            (idx.1 & Self::MASK) as f32,
+            0.0,
+        ]
+    }
+
+    fn axes(&self) -> [[f32; 3]; 3] {
+        [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+    }
+
+    fn periods(&self) -> [Option<f32>; 3] {
+        [Some(SIDE as f32), Some(SIDE as f32), None]
+    }
+}
+
+impl<T: Copy + RandAtom, const SIDE: usize, const POW: usize> Grid for FastArray<T, SIDE, POW> {
+    fn grid_shape(&self) -> Vec<usize> {
+        vec![SIDE, SIDE]
+    }
 }
 
 impl<const SIDE: usize, const POW: usize> GifFrame for FastArray<BinAtom, SIDE, POW> {
@@ -0,0 +1,156 @@
+use std::{
+    f64::consts::PI,
+    ops::{Add, Mul, Sub},
+};
+
+/// A complex number for the fourier transforms.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    /// exp(i * phase)
+    pub fn expi(phase: f64) -> Self {
+        Self::new(phase.cos(), phase.sin())
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    pub fn scale(self, factor: f64) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+/// This function replaces `data` with its discrete fourier transform
+/// X_k = sum_n x_n exp(-2 pi i k n / N).
+/// Powers of two use the radix-2 algorithm, all other lengths Bluestein's algorithm.
+pub(crate) fn fft(data: &mut [Complex]) {
+    if data.len().is_power_of_two() {
+        radix_2(data, false)
+    } else {
+        bluestein(data)
+    }
+}
+
+/// This function transforms `data` along every axis, the axes have the lengths `shape`
+/// from the slowest to the fastest varying.
+pub(crate) fn fft_nd(data: &mut [Complex], shape: &[usize]) {
+    assert_eq!(data.len(), shape.iter().product::<usize>());
+    let mut line = Vec::new();
+    for (axis, len) in shape.iter().copied().enumerate() {
+        let stride = shape[axis + 1..].iter().product::<usize>();
+        for start in 0..data.len() {
+            // only the starts of the lines along this axis
+            if (start / stride) % len != 0 {
+                continue;
+            }
+            line.clear();
+            line.extend((0..len).map(|i| data[start + i * stride]));
+            fft(&mut line);
+            for (i, value) in line.iter().enumerate() {
+                data[start + i * stride] = *value;
+            }
+        }
+    }
+}
+
+/// the iterative Cooley-Tukey algorithm, the inverse isn't normalized
+fn radix_2(data: &mut [Complex], inverse: bool) {
+    let n = data.len();
+    debug_assert!(n.is_power_of_two());
+    if n < 2 {
+        return;
+    }
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let step = Complex::expi(sign * 2.0 * PI / len as f64);
+        for chunk in data.chunks_mut(len) {
+            let mut w = Complex::new(1.0, 0.0);
+            let (lower, upper) = chunk.split_at_mut(len / 2);
+            for (a, b) in lower.iter_mut().zip(upper) {
+                let t = w * *b;
+                *b = *a - t;
+                *a = *a + t;
+                w = w * step;
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// Bluestein's algorithm writes the transform as a convolution,
+/// which is calculated with radix-2 transforms of at least twice the length.
+fn bluestein(data: &mut [Complex]) {
+    let n = data.len();
+    let m = (2 * n - 1).next_power_of_two();
+    // exp(-i pi k^2 / n), k^2 is reduced modulo 2n to keep the phase exact
+    let chirp: Vec<Complex> = (0..n)
+        .map(|k| Complex::expi(-PI * ((k * k) % (2 * n)) as f64 / n as f64))
+        .collect();
+    let mut a = vec![Complex::default(); m];
+    for (k, value) in data.iter().enumerate() {
+        a[k] = *value * chirp[k];
+    }
+    let mut b = vec![Complex::default(); m];
+    b[0] = chirp[0].conj();
+    for k in 1..n {
+        b[k] = chirp[k].conj();
+        b[m - k] = chirp[k].conj();
+    }
+    radix_2(&mut a, false);
+    radix_2(&mut b, false);
+    for (a, b) in a.iter_mut().zip(&b) {
+        *a = *a * *b;
+    }
+    radix_2(&mut a, true);
+    for (k, value) in data.iter_mut().enumerate() {
+        *value = a[k].scale(1.0 / m as f64) * chirp[k];
+    }
+}
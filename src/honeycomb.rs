@@ -0,0 +1,207 @@
+use std::{
+    collections::HashMap,
+    ops::{Index, IndexMut},
+};
+
+use crate::{Array2d, BinAtom, Geometry, GifFrame, Lattice, MyRng, RandAtom};
+
+/// For the next nearest neighbors one offset of each pair (o, -o).
+const NEXT_NEAREST_OFFSETS: [(isize, isize); 3] = [(2, 0), (1, 1), (1, -1)];
+
+/// A honeycomb lattice where every site has 3 nearest neighbors.
+/// The sites are stored as a brick wall on top of an `Array2d`:
+/// every site (x, y) is bonded to (x + 1, y) and (x - 1, y) and to (x, y + 1)
+/// if x + y is even or to (x, y - 1) if x + y is odd.
+/// W and H need to be even for the wrapping to be consistent.
+#[derive(Clone)]
+pub struct Honeycomb<T, const W: usize, const H: usize>(Array2d<T, W, H>);
+
+impl<T: Copy + Default, const W: usize, const H: usize> Honeycomb<T, W, H> {
+    pub fn new() -> Self {
+        assert!(
+            W.is_multiple_of(2) && H.is_multiple_of(2),
+            "W and H need to be even"
+        );
+        Self(Array2d::new())
+    }
+}
+
+impl<T, const W: usize, const H: usize> Honeycomb<T, W, H> {
+    fn vertical_neighbor(idx: (isize, isize)) -> (isize, isize) {
+        let (x, y) = idx;
+        if (x + y).rem_euclid(2) == 0 {
+            (x, y + 1)
+        } else {
+            (x, y - 1)
+        }
+    }
+}
+
+impl<T, const W: usize, const H: usize> Index<(isize, isize)> for Honeycomb<T, W, H> {
+    type Output = T;
+
+    fn index(&self, index: (isize, isize)) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<T, const W: usize, const H: usize> IndexMut<(isize, isize)> for Honeycomb<T, W, H> {
+    fn index_mut(&mut self, index: (isize, isize)) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl<T: Default + Copy, const W: usize, const H: usize> Default for Honeycomb<T, W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + RandAtom, const W: usize, const H: usize> Lattice for Honeycomb<T, W, H> {
+    type Atom = T;
+    type Index = (isize, isize);
+    type Neighbors = [Self::Index; 3];
+    type Shape = ();
+
+    fn fill_value(_shape: Self::Shape, val: Self::Atom) -> Self {
+        assert!(
+            W.is_multiple_of(2) && H.is_multiple_of(2),
+            "W and H need to be even"
+        );
+        Self(Array2d::fill_value(Default::default(), val))
+    }
+
+    fn fill_with_fn(_shape: Self::Shape, func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self {
+        assert!(
+            W.is_multiple_of(2) && H.is_multiple_of(2),
+            "W and H need to be even"
+        );
+        Self(Array2d::fill_with_fn(Default::default(), func))
+    }
+
+    fn shape(&self) -> Self::Shape {}
+
+    fn all_neighbors(&self) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        let mut out = HashMap::new();
+        for x in 0..W as isize {
+            for y in 0..H as isize {
+                out.entry((self[(x, y)], self[(x - 1, y)]))
+                    .and_modify(|e| *e += 1)
+                    .or_insert(1);
+                // every vertical bond has exactly one site with an even x + y
+                if (x + y) % 2 == 0 {
+                    out.entry((self[(x, y)], self[(x, y + 1)]))
+                        .and_modify(|e| *e += 1)
+                        .or_insert(1);
+                }
+            }
+        }
+        out
+    }
+
+    fn all_neighbors_to(&self, idx: Self::Index) -> Self::Neighbors {
+        [
+            (idx.0 + 1, idx.1),
+            (idx.0 - 1, idx.1),
+            Self::vertical_neighbor(idx),
+        ]
+    }
+
+    fn shells(&self) -> usize {
+        2
+    }
+
+    fn all_shell_neighbors(&self, shell: usize) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        if shell == 0 {
+            return self.all_neighbors();
+        }
+        assert_eq!(shell, 1, "this lattice only provides two shells");
+        let mut out = HashMap::new();
+        for x in 0..W as isize {
+            for y in 0..H as isize {
+                for (d_x, d_y) in NEXT_NEAREST_OFFSETS {
+                    out.entry((self[(x, y)], self[(x - d_x, y - d_y)]))
+                        .and_modify(|e| *e += 1)
+                        .or_insert(1);
+                }
+            }
+        }
+        out
+    }
+
+    fn shell_neighbors_to(&self, idx: Self::Index, shell: usize) -> Vec<Self::Index> {
+        if shell == 0 {
+            return self.all_neighbors_to(idx).to_vec();
+        }
+        assert_eq!(shell, 1, "this lattice only provides two shells");
+        NEXT_NEAREST_OFFSETS
+            .iter()
+            .flat_map(|(d_x, d_y)| [(idx.0 + d_x, idx.1 + d_y), (idx.0 - d_x, idx.1 - d_y)])
+            .collect()
+    }
+
+    fn all_idxs(&self) -> Vec<Self::Index> {
+        self.0.all_idxs()
+    }
+
+    fn tot_sites(&self) -> usize {
+        W * H
+    }
+
+    fn as_flat_slice(&self) -> &[Self::Atom] {
+        self.0.as_flat_slice()
+    }
+
+    fn as_flat_slice_mut(&mut self) -> &mut [Self::Atom] {
+        self.0.as_flat_slice_mut()
+    }
+
+    fn flat_index(&self, idx: Self::Index) -> usize {
+        self.0.flat_index(idx)
+    }
+
+    fn random_idx(&self, rng: &mut MyRng) -> Self::Index {
+        self.0.random_idx(rng)
+    }
+
+    fn reduce_index(&self, idx: Self::Index) -> Self::Index {
+        self.0.reduce_index(idx)
+    }
+
+    fn translate(&self, idx: Self::Index, by: Self::Index) -> Option<Self::Index> {
+        self.0.translate(idx, by)
+    }
+}
+
+impl<T: Copy + RandAtom, const W: usize, const H: usize> Geometry for Honeycomb<T, W, H> {
+    /// the sites with an even x + y lie a third of a row above the others
+    fn coordinates(&self, idx: Self::Index) -> [f32; 3] {
+        let (x, y) = self.reduce_index(idx);
+        let shift = if (x + y).rem_euclid(2) == 0 {
+            1.0 / 3.0
+        } else {
+            0.0
+        };
+        [x as f32, y as f32 + shift, 0.0]
+    }
+
+    /// the nearest neighbors have the distance 1
+    fn axes(&self) -> [[f32; 3]; 3] {
+        [
+            [3.0_f32.sqrt() / 2.0, 0.0, 0.0],
+            [0.0, 1.5, 0.0],
+            [0.0, 0.0, 1.0],
+        ]
+    }
+
+    fn periods(&self) -> [Option<f32>; 3] {
+        self.0.periods()
+    }
+}
+
+impl<const W: usize, const H: usize> GifFrame for Honeycomb<BinAtom, W, H> {
+    /// the frame shows the brick wall representation of the lattice
+    fn get_frame(&self) -> gif::Frame<'_> {
+        self.0.get_frame()
+    }
+}
@@ -4,23 +4,61 @@ use std::{
     process::Command,
 };
 
+use rand::distributions::Distribution;
 use rand_pcg::Pcg64;
 
+mod acceptance;
+pub use acceptance::AcceptanceRule;
 mod array_2d;
 pub use array_2d::Array2d;
 mod array_3d;
 pub use array_3d::Array3d;
+mod cubic;
+pub use cubic::{Bcc, BodyCentered, Cubic, CubicBasis, FaceCentered, Fcc};
+mod dyn_array_2d;
+pub use dyn_array_2d::{DynArray2d, Size2d};
+mod dyn_array_3d;
+pub use dyn_array_3d::{DynArray3d, Size3d};
 mod fast_array;
 pub use fast_array::FastArray;
+mod honeycomb;
+pub use honeycomb::Honeycomb;
+mod triangular;
+pub use triangular::Triangular;
+
+mod stats;
+pub use stats::{StatsSummary, StreamingStats};
+mod fft;
+mod structure_factor;
+pub use structure_factor::StructureFactor;
+mod pair_correlation;
+pub use pair_correlation::PairCorrelation;
+mod short_range_order;
+pub use short_range_order::ShortRangeOrder;
+mod order_parameter;
+pub use order_parameter::{OrderParameter, OrderParameterMoments};
+mod equilibration;
+pub use equilibration::{detect_equilibration, statistical_inefficiency, Equilibration};
 
 mod atoms;
-pub use atoms::{BinAtom, BinConcentration, Energies, Mark, RandAtom};
+pub use atoms::{
+    BinAtom, BinConcentration, Energies, Mark, MultiAtom, MultiConcentration, PairEnergies,
+    RandAtom, ShellEnergies, VacancyEnergies,
+};
 
 mod system;
-pub use system::System;
+pub use system::{NoUnlikePairs, System};
+mod tempering;
+pub use tempering::ReplicaExchange;
+mod reweighting;
+pub use reweighting::{DensityOfStates, EnergyHistogram, NotConverged};
+mod wang_landau;
+pub use wang_landau::{ln_multinomial, Thermodynamics, WangLandau, WangLandauMove};
 
 pub mod anim;
 pub mod logs;
+pub mod schedule;
+pub use schedule::{Observer, TemperatureSchedule};
 
 type MyRng = Pcg64;
 
@@ -28,29 +66,69 @@ pub trait Lattice: Index<Self::Index, Output = Self::Atom> + IndexMut<Self::Inde
     type Atom: Copy + RandAtom;
     type Index: Copy;
     type Neighbors: AsRef<[Self::Index]>;
+    /// Everything needed to construct the lattice which is not known at compile time.
+    type Shape: Copy;
 
-    fn fill_value(val: Self::Atom) -> Self;
-    fn fill_with_fn(func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self;
+    fn fill_value(shape: Self::Shape, val: Self::Atom) -> Self;
+    fn fill_with_fn(shape: Self::Shape, func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self;
+    fn shape(&self) -> Self::Shape;
 
     fn all_neighbors(&self) -> HashMap<(Self::Atom, Self::Atom), u32>;
     fn all_neighbors_to(&self, idx: Self::Index) -> Self::Neighbors;
 
+    /// The number of neighbor shells this lattice provides.
+    /// Shell 0 are the nearest neighbors, shell 1 the next nearest neighbors and so on.
+    fn shells(&self) -> usize {
+        1
+    }
+    /// Like `all_neighbors` but for the neighbors in `shell`.
+    fn all_shell_neighbors(&self, shell: usize) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        assert_eq!(shell, 0, "this lattice only provides nearest neighbors");
+        self.all_neighbors()
+    }
+    /// Like `all_neighbors_to` but for the neighbors in `shell`.
+    fn shell_neighbors_to(&self, idx: Self::Index, shell: usize) -> Vec<Self::Index> {
+        assert_eq!(shell, 0, "this lattice only provides nearest neighbors");
+        self.all_neighbors_to(idx).as_ref().to_vec()
+    }
+
+    /// The atoms of the fixed walls next to `idx`, see `Boundary::Wall`.
+    /// Walls only interact with their nearest neighbors.
+    fn wall_neighbors_to(&self, _idx: Self::Index) -> Vec<Self::Atom> {
+        Vec::new()
+    }
+
     fn all_idxs(&self) -> Vec<Self::Index>;
     fn tot_sites(&self) -> usize;
 
     fn as_flat_slice(&self) -> &[Self::Atom];
     fn as_flat_slice_mut(&mut self) -> &mut [Self::Atom];
+    /// the position of the site at idx in `as_flat_slice`
+    fn flat_index(&self, idx: Self::Index) -> usize;
 
     fn random_idx(&self, rng: &mut MyRng) -> Self::Index;
     fn choose_idxs_uniformly(&self, rng: &mut MyRng) -> (Self::Index, Self::Index) {
         (self.random_idx(rng), self.random_idx(rng))
     }
-    // fn choose_idxs_with_distribution(
-    //     &self,
-    //     rng: &mut MyRng,
-    //     distr: impl Distribution<Self::Index>,
-    // ) -> (Self::Index, Self::Index);
+    /// This function chooses a random site and a second site which is displaced from the first
+    /// by an offset drawn from `distr`, see `translate`.
+    /// If the second site isn't on the lattice both sites are drawn again.
+    fn choose_idxs_with_distribution(
+        &self,
+        rng: &mut MyRng,
+        distr: impl Distribution<Self::Index>,
+    ) -> (Self::Index, Self::Index) {
+        loop {
+            let idx = self.random_idx(rng);
+            if let Some(other) = self.translate(idx, distr.sample(rng)) {
+                return (idx, other);
+            }
+        }
+    }
     fn reduce_index(&self, idx: Self::Index) -> Self::Index;
+    /// This function returns the site at idx displaced by the offset `by`,
+    /// or None if this site leaves the lattice along an axis which isn't periodic.
+    fn translate(&self, idx: Self::Index, by: Self::Index) -> Option<Self::Index>;
     fn swap_vals(&mut self, idx_1: Self::Index, idx_2: Self::Index) {
         let temp = self[idx_1];
         self[idx_1] = self[idx_2];
@@ -58,10 +136,86 @@ pub trait Lattice: Index<Self::Index, Output = Self::Atom> + IndexMut<Self::Inde
     }
 }
 
+/// The boundary condition along one axis of a lattice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Boundary<T> {
+    /// the indexes "wrap around"
+    #[default]
+    Periodic,
+    /// the sites at the edge have fewer neighbors
+    Free,
+    /// the sites at the edge are bonded to a wall made from this atom,
+    /// the wall never changes
+    Wall(T),
+}
+
+impl<T> Boundary<T> {
+    pub fn is_periodic(&self) -> bool {
+        matches!(self, Boundary::Periodic)
+    }
+}
+
+/// The neighbors of a site for lattices where not every site has the same number of neighbors.
+#[derive(Clone, Copy)]
+pub struct NeighborList<I, const N: usize> {
+    idxs: [I; N],
+    len: usize,
+}
+
+impl<I: Copy, const N: usize> NeighborList<I, N> {
+    /// `fill` is only used to initialize the unused entries
+    pub fn new(fill: I) -> Self {
+        Self {
+            idxs: [fill; N],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, idx: I) {
+        self.idxs[self.len] = idx;
+        self.len += 1;
+    }
+}
+
+impl<I, const N: usize> AsRef<[I]> for NeighborList<I, N> {
+    fn as_ref(&self) -> &[I] {
+        &self.idxs[..self.len]
+    }
+}
+
 pub trait GifFrame: Lattice {
     fn get_frame(&self) -> gif::Frame<'_>;
 }
 
+/// Lattices whose sites have positions in space, see `PairCorrelation`.
+/// The position of a site is sum_i coordinates_i * axes_i.
+pub trait Geometry: Lattice {
+    /// the coordinates of the site at idx along the axes, the lattice spans [0, period)
+    /// along every axis
+    fn coordinates(&self, idx: Self::Index) -> [f32; 3];
+    /// the vectors of the axes in space, for 2d lattices the third axis is (0, 0, 1)
+    fn axes(&self) -> [[f32; 3]; 3];
+    /// the length of every axis in coordinates or None if it isn't periodic
+    fn periods(&self) -> [Option<f32>; 3];
+
+    /// the distance between the sites with the minimum image convention
+    fn distance(&self, idx_1: Self::Index, idx_2: Self::Index) -> f32 {
+        let (c_1, c_2) = (self.coordinates(idx_1), self.coordinates(idx_2));
+        pair_correlation::minimum_image_distance(
+            [c_2[0] - c_1[0], c_2[1] - c_1[1], c_2[2] - c_1[2]],
+            &self.axes(),
+            &self.periods(),
+        )
+    }
+}
+
+/// Lattices whose sites form a rectangular grid in `as_flat_slice`, see `StructureFactor`.
+pub trait Grid: Lattice {
+    /// the side lengths of the grid from the slowest to the fastest varying axis
+    /// in `as_flat_slice`
+    fn grid_shape(&self) -> Vec<usize>;
+}
+
 // Todo why BTreeMap?
 pub struct ClusterDistribution(BTreeMap<u32, u32>);
 
@@ -93,6 +247,12 @@ impl ClusterDistribution {
     }
 }
 
+impl Default for ClusterDistribution {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub trait ClusterCounter: Lattice
 where
     <Self as Lattice>::Atom: Mark,
@@ -145,6 +305,18 @@ where
     }
 }
 
+/// This function prepends `prefix` to all `names`, it is used for the categories of the logs.
+pub(crate) fn prefixed(prefix: Option<impl ToString>, mut names: Vec<String>) -> Vec<String> {
+    if let Some(prefix) = prefix {
+        names.iter_mut().for_each(|string| {
+            let mut temp = prefix.to_string();
+            temp.push_str(string);
+            *string = temp;
+        })
+    }
+    names
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ClusterStats {
     min: u32,
@@ -225,22 +397,16 @@ impl ClusterStats {
     }
 
     pub fn get_categories(prefix: Option<impl ToString>) -> Vec<String> {
-        let mut out =
+        prefixed(
+            prefix,
             vec![
                 "min".to_owned(),
                 "quart_1".to_owned(),
                 "median".to_owned(),
                 "quart_3".to_owned(),
                 "max".to_owned(),
-            ];
-        if let Some(prefix) = prefix {
-            out.iter_mut().for_each(|string| {
-                let mut temp = prefix.to_string();
-                temp.push_str(string);
-                *string = temp;
-            })
-        }
-        out
+            ],
+        )
     }
 
     pub fn as_vec_f32(&self) -> Vec<f32> {
@@ -297,41 +463,3 @@ pub fn flatten<T, const N: usize, const M: usize>(arr: &[[T; N]; M]) -> &[T] {
     }
     unsafe { std::slice::from_raw_parts(arr.as_ptr().cast(), N * M) }
 }
-
-pub struct StreamingStats {
-    count: u32,
-    m_k: f64,
-    m_k_1: f64,
-    v_k: f64,
-    v_k_1: f64,
-}
-
-impl StreamingStats {
-    // variance after https://math.stackexchange.com/questions/20593/calculate-variance-from-a-stream-of-sample-values
-    pub fn new() -> Self {
-        Self {
-            count: 0,
-            m_k: 0.0,
-            m_k_1: 0.0,
-            v_k: 0.0,
-            v_k_1: 0.0,
-        }
-    }
-    // _1 is old
-    pub fn add_value(&mut self, x_k: f32) {
-        let x_k = x_k as f64;
-        self.count += 1;
-        self.m_k_1 = self.m_k;
-        self.v_k_1 = self.v_k;
-        self.m_k = self.m_k_1 + (x_k - self.m_k_1) / self.count as f64;
-        self.v_k = self.v_k_1 + (x_k - self.m_k_1) * (x_k - self.m_k);
-    }
-
-    pub fn avg(&self) -> f32 {
-        self.m_k as f32
-    }
-
-    pub fn variance(&self) -> f32 {
-        (self.v_k / self.count as f64) as f32
-    }
-}
@@ -0,0 +1,199 @@
+use crate::{prefixed, Lattice, RandAtom, StreamingStats};
+
+/// An order parameter m = sum_i w_i s_i / sum_i |w_i| of one species,
+/// where s_i is 1 if the site holds the atom, -1 if it holds another atom and 0 for vacancies
+/// and w_i is the weight of the sublattice of the site.
+#[derive(Debug, Clone)]
+pub struct OrderParameter<A> {
+    atom: A,
+    /// the weight of every site in the order of `as_flat_slice`
+    weights: Vec<f32>,
+    norm: f32,
+}
+
+impl<A: RandAtom + Copy> OrderParameter<A> {
+    /// the global composition order parameter m = c_atom - c_others
+    pub fn composition<L: Lattice<Atom = A>>(lattice: &L, atom: A) -> Self {
+        Self::sublattices(lattice, atom, |_| 1.0)
+    }
+
+    /// The checkerboard order parameter, the two sublattices are the two colors of the
+    /// nearest neighbor bonds and have the weights 1 and -1.
+    /// This needs every nearest neighbor of a site to be on the other sublattice,
+    /// which isn't the case for triangular or fcc lattices or periodic axes of odd length.
+    pub fn checkerboard<L: Lattice<Atom = A>>(lattice: &L, atom: A) -> Self {
+        let mut colors: Vec<Option<bool>> = vec![None; lattice.tot_sites()];
+        for start in lattice.all_idxs() {
+            if colors[lattice.flat_index(start)].is_some() {
+                continue;
+            }
+            colors[lattice.flat_index(start)] = Some(true);
+            let mut stack = vec![start];
+            while let Some(idx) = stack.pop() {
+                let color = colors[lattice.flat_index(idx)].expect("the site is colored");
+                for neighbor in lattice.all_neighbors_to(idx).as_ref() {
+                    let other = &mut colors[lattice.flat_index(*neighbor)];
+                    match other {
+                        Some(other) => assert!(
+                            *other != color,
+                            "the lattice can't be split into two sublattices"
+                        ),
+                        None => {
+                            *other = Some(!color);
+                            stack.push(*neighbor);
+                        }
+                    }
+                }
+            }
+        }
+        let weights = colors
+            .into_iter()
+            .map(|color| if color == Some(true) { 1.0 } else { -1.0 })
+            .collect();
+        Self::with_weights(atom, weights)
+    }
+
+    /// An order parameter of arbitrary sublattices, `weight` gives the weight of every site.
+    /// For example the weights 1 for one sublattice and 0 for all other sites give the
+    /// composition of the sublattice.
+    pub fn sublattices<L: Lattice<Atom = A>>(
+        lattice: &L,
+        atom: A,
+        mut weight: impl FnMut(L::Index) -> f32,
+    ) -> Self {
+        let mut weights = vec![0.0; lattice.tot_sites()];
+        for idx in lattice.all_idxs() {
+            weights[lattice.flat_index(idx)] = weight(idx);
+        }
+        Self::with_weights(atom, weights)
+    }
+
+    fn with_weights(atom: A, weights: Vec<f32>) -> Self {
+        let norm = weights.iter().map(|weight| weight.abs()).sum::<f32>();
+        assert!(norm > 0.0, "at least one weight has to be non zero");
+        Self {
+            atom,
+            weights,
+            norm,
+        }
+    }
+
+    pub fn atom(&self) -> A {
+        self.atom
+    }
+
+    /// the value of the order parameter for the configuration of the lattice
+    pub fn value<L: Lattice<Atom = A>>(&self, lattice: &L) -> f32 {
+        let sites = lattice.as_flat_slice();
+        assert_eq!(
+            sites.len(),
+            self.weights.len(),
+            "the order parameter belongs to a different lattice"
+        );
+        sites
+            .iter()
+            .zip(&self.weights)
+            .map(|(site, weight)| {
+                if *site == self.atom {
+                    *weight
+                } else if site.is_vacancy() {
+                    0.0
+                } else {
+                    -weight
+                }
+            })
+            .sum::<f32>()
+            / self.norm
+    }
+}
+
+/// The moments <|m|>, <m^2> and <m^4> of a stream of values of an order parameter.
+pub struct OrderParameterMoments {
+    abs: StreamingStats,
+    squared: StreamingStats,
+}
+
+impl OrderParameterMoments {
+    pub fn new() -> Self {
+        Self {
+            abs: StreamingStats::new(),
+            squared: StreamingStats::new(),
+        }
+    }
+
+    pub fn add_value(&mut self, m: f32) {
+        self.abs.add_value(m.abs());
+        self.squared.add_value(m * m);
+    }
+
+    /// the statistics of |m|, including its error
+    pub fn abs(&self) -> &StreamingStats {
+        &self.abs
+    }
+
+    /// the statistics of m^2, including its error
+    pub fn squared(&self) -> &StreamingStats {
+        &self.squared
+    }
+
+    pub fn abs_mean(&self) -> f32 {
+        self.abs.avg()
+    }
+
+    pub fn second_moment(&self) -> f32 {
+        self.squared.avg()
+    }
+
+    pub fn fourth_moment(&self) -> f32 {
+        self.squared.variance() + self.squared.avg().powi(2)
+    }
+
+    /// the Binder cumulant U = 1 - <m^4> / (3 <m^2>^2),
+    /// for large systems it is 2/3 in the ordered and 0 in the disordered phase
+    pub fn binder_cumulant(&self) -> f32 {
+        1.0 - self.fourth_moment() / (3.0 * self.second_moment().powi(2))
+    }
+
+    /// the Binder cumulant and its error from the jackknife, see `StreamingStats::jackknife`
+    pub fn binder_cumulant_with_error(&self) -> (f32, f32) {
+        self.squared
+            .jackknife(|m_2, m_4| 1.0 - m_4 / (3.0 * m_2 * m_2))
+    }
+
+    /// the susceptibility chi = beta * sites * (<m^2> - <|m|>^2)
+    pub fn susceptibility(&self, beta: f32, sites: usize) -> f32 {
+        beta * sites as f32 * (self.second_moment() - self.abs_mean().powi(2))
+    }
+
+    pub fn get_categories(prefix: Option<impl ToString>) -> Vec<String> {
+        prefixed(
+            prefix,
+            vec![
+                "abs mean".to_owned(),
+                "abs mean error".to_owned(),
+                "second moment".to_owned(),
+                "fourth moment".to_owned(),
+                "binder cumulant".to_owned(),
+                "binder cumulant error".to_owned(),
+            ],
+        )
+    }
+
+    pub fn as_vec_f32(&self) -> Vec<f32> {
+        let (binder_cumulant, binder_cumulant_error) = self.binder_cumulant_with_error();
+        vec![
+            self.abs_mean(),
+            self.abs.error(),
+            self.second_moment(),
+            self.fourth_moment(),
+            binder_cumulant,
+            binder_cumulant_error,
+        ]
+    }
+}
+
+impl Default for OrderParameterMoments {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,304 @@
+use crate::{Geometry, RandAtom};
+
+/// The species-species pair correlation functions g_ij(r) = P(j at r | i at 0) / c_j,
+/// where P(j at r | i at 0) is the probability that an atom at the distance r from an i atom
+/// is a j atom and c_j is the concentration of j.
+/// g_ij(r) is 1 for a random alloy, for the neighbor shells it is 1 - alpha_ij,
+/// see `ShortRangeOrder`.
+/// The distances follow the minimum image convention and are collected in bins,
+/// vacancies are ignored.
+#[derive(Debug, Clone)]
+pub struct PairCorrelation<A> {
+    species: Vec<A>,
+    bin_width: f32,
+    atom_counts: Vec<u64>,
+    /// the counts of the ordered pairs of species of every bin, every pair is counted both ways
+    pair_counts: Vec<Vec<u64>>,
+    distance_sums: Vec<f64>,
+}
+
+impl<A: RandAtom + Copy> PairCorrelation<A> {
+    /// This function counts all pairs of sites up to `max_distance`, which should be at most
+    /// half of the periods of the lattice.
+    /// The pairs are found with a cell list, so this is fast for small `max_distance`.
+    pub fn new<L: Geometry<Atom = A>>(lattice: &L, max_distance: f32, bin_width: f32) -> Self {
+        assert!(max_distance > 0.0 && bin_width > 0.0);
+        let species = A::all_atoms();
+        let n = species.len();
+        let axes = lattice.axes();
+        let periods = lattice.periods();
+        let sites: Vec<([f32; 3], usize)> = lattice
+            .all_idxs()
+            .into_iter()
+            .filter_map(|idx| {
+                let i = species
+                    .iter()
+                    .position(|species| *species == lattice[idx])?;
+                Some((lattice.coordinates(idx), i))
+            })
+            .collect();
+        let mut atom_counts = vec![0; n];
+        for (_, i) in sites.iter() {
+            atom_counts[*i] += 1;
+        }
+
+        let cells = CellList::new(&sites, max_distance, &axes, &periods);
+        let bins = (max_distance / bin_width).ceil() as usize;
+        let mut pair_counts = vec![vec![0; n * n]; bins];
+        let mut distance_sums = vec![0.0; bins];
+        for (cell, members) in cells.members.iter().enumerate() {
+            for neighbor in cells.neighbors(cell) {
+                for i in members.iter().copied() {
+                    let (c_i, s_i) = sites[i];
+                    for j in cells.members[neighbor].iter().copied() {
+                        if i == j {
+                            continue;
+                        }
+                        let (c_j, s_j) = sites[j];
+                        let diff = [c_j[0] - c_i[0], c_j[1] - c_i[1], c_j[2] - c_i[2]];
+                        let distance = minimum_image_distance(diff, &axes, &periods);
+                        if distance <= max_distance {
+                            let bin = ((distance / bin_width) as usize).min(bins - 1);
+                            pair_counts[bin][s_i * n + s_j] += 1;
+                            distance_sums[bin] += distance as f64;
+                        }
+                    }
+                }
+            }
+        }
+        Self {
+            species,
+            bin_width,
+            atom_counts,
+            pair_counts,
+            distance_sums,
+        }
+    }
+
+    /// This function adds the pairs of `other`, for example to average over configurations.
+    pub fn combine(&mut self, other: &Self) {
+        assert!(
+            self.bin_width == other.bin_width && self.pair_counts.len() == other.pair_counts.len(),
+            "the bins have to be the same"
+        );
+        for (count, other) in self.atom_counts.iter_mut().zip(&other.atom_counts) {
+            *count += other;
+        }
+        for (counts, other) in self.pair_counts.iter_mut().zip(&other.pair_counts) {
+            for (count, other) in counts.iter_mut().zip(other) {
+                *count += other;
+            }
+        }
+        for (sum, other) in self.distance_sums.iter_mut().zip(&other.distance_sums) {
+            *sum += other;
+        }
+    }
+
+    fn position(&self, atom: A) -> usize {
+        self.species
+            .iter()
+            .position(|species| *species == atom)
+            .expect("the atom is a species of the lattice")
+    }
+
+    /// the mean distance and g(r) of the pair of atoms for every bin which contains pairs
+    pub fn g(&self, a_1: A, a_2: A) -> Vec<(f32, f32)> {
+        let n = self.species.len();
+        let (i, j) = (self.position(a_1), self.position(a_2));
+        let c_j = self.atom_counts[j] as f64 / self.atom_counts.iter().sum::<u64>() as f64;
+        self.pair_counts
+            .iter()
+            .zip(&self.distance_sums)
+            .filter_map(|(counts, distance_sum)| {
+                let neighbors_of_i = counts[i * n..(i + 1) * n].iter().sum::<u64>();
+                if neighbors_of_i == 0 || c_j == 0.0 {
+                    return None;
+                }
+                let distance = distance_sum / counts.iter().sum::<u64>() as f64;
+                let g = counts[i * n + j] as f64 / neighbors_of_i as f64 / c_j;
+                Some((distance as f32, g as f32))
+            })
+            .collect()
+    }
+
+    /// This function fits |g(r) - 1| ~ exp(-r / xi) to the pair of atoms and returns the
+    /// correlation length xi.
+    /// Only the leading bins where |g(r) - 1| decreases are fitted, further out the noise and
+    /// the fixed composition of the finite lattice dominate.
+    /// If the correlations don't decay, xi is infinite.
+    pub fn correlation_length(&self, a_1: A, a_2: A) -> f32 {
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        for (r, g) in self.g(a_1, a_2) {
+            let h = (g as f64 - 1.0).abs();
+            match points.last() {
+                Some((_, last)) if h >= last.exp() => break,
+                _ if h == 0.0 => break,
+                _ => points.push((r as f64, h.ln())),
+            }
+        }
+        if points.len() < 2 {
+            return f32::INFINITY;
+        }
+        let n = points.len() as f64;
+        let mean_r = points.iter().map(|(r, _)| r).sum::<f64>() / n;
+        let mean_ln = points.iter().map(|(_, ln)| ln).sum::<f64>() / n;
+        let covariance = points
+            .iter()
+            .map(|(r, ln)| (r - mean_r) * (ln - mean_ln))
+            .sum::<f64>();
+        let variance = points
+            .iter()
+            .map(|(r, _)| (r - mean_r).powi(2))
+            .sum::<f64>();
+        let slope = covariance / variance;
+        if slope >= 0.0 {
+            f32::INFINITY
+        } else {
+            (-1.0 / slope) as f32
+        }
+    }
+}
+
+/// This function returns the length of the shortest periodic image of the difference `diff`
+/// of the coordinates of two sites.
+pub(crate) fn minimum_image_distance(
+    mut diff: [f32; 3],
+    axes: &[[f32; 3]; 3],
+    periods: &[Option<f32>; 3],
+) -> f32 {
+    for (d, period) in diff.iter_mut().zip(periods) {
+        if let Some(period) = period {
+            *d -= period * (*d / period).round();
+        }
+    }
+    let length = |diff: [f32; 3]| {
+        (0..3)
+            .map(|k| (0..3).map(|i| diff[i] * axes[i][k]).sum::<f32>().powi(2))
+            .sum::<f32>()
+            .sqrt()
+    };
+    let dot = |a: &[f32; 3], b: &[f32; 3]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f32>();
+    if dot(&axes[0], &axes[1]) == 0.0
+        && dot(&axes[0], &axes[2]) == 0.0
+        && dot(&axes[1], &axes[2]) == 0.0
+    {
+        return length(diff);
+    }
+    // for skewed axes an image one period further can be shorter
+    let images = |axis: usize| match periods[axis] {
+        Some(period) => [-period, 0.0, period],
+        None => [0.0; 3],
+    };
+    let mut shortest = f32::INFINITY;
+    for i in images(0) {
+        for j in images(1) {
+            for k in images(2) {
+                shortest = shortest.min(length([diff[0] + i, diff[1] + j, diff[2] + k]));
+            }
+        }
+    }
+    shortest
+}
+
+/// The sites sorted into cells along the axes, such that all sites up to the maximal distance
+/// of a site lie in its own cell or in the neighboring cells.
+struct CellList {
+    shape: [usize; 3],
+    periodic: [bool; 3],
+    members: Vec<Vec<usize>>,
+}
+
+impl CellList {
+    fn new(
+        sites: &[([f32; 3], usize)],
+        max_distance: f32,
+        axes: &[[f32; 3]; 3],
+        periods: &[Option<f32>; 3],
+    ) -> Self {
+        // a vector of length r has at most the coordinate r * |b_i| along axis i,
+        // where b_i is the reciprocal vector |a_j x a_k| / V
+        let cross = |a: [f32; 3], b: [f32; 3]| {
+            [
+                a[1] * b[2] - a[2] * b[1],
+                a[2] * b[0] - a[0] * b[2],
+                a[0] * b[1] - a[1] * b[0],
+            ]
+        };
+        let norm = |a: [f32; 3]| a.iter().map(|a| a * a).sum::<f32>().sqrt();
+        let cross_12 = cross(axes[1], axes[2]);
+        let volume = (0..3).map(|k| axes[0][k] * cross_12[k]).sum::<f32>().abs();
+        let reciprocal = [
+            norm(cross_12) / volume,
+            norm(cross(axes[2], axes[0])) / volume,
+            norm(cross(axes[0], axes[1])) / volume,
+        ];
+
+        let mut origin = [0.0; 3];
+        let mut widths = [1.0; 3];
+        let mut shape = [1; 3];
+        for axis in 0..3 {
+            let (min, max) = sites
+                .iter()
+                .map(|(coords, _)| coords[axis])
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), c| {
+                    (min.min(c), max.max(c))
+                });
+            let (start, extent) = match periods[axis] {
+                Some(period) => (0.0, period),
+                None => (min, max - min + 1.0),
+            };
+            let cells = (extent / (max_distance * reciprocal[axis])).floor() as usize;
+            // with less than 3 cells the neighboring cells would be the same
+            shape[axis] = if cells < 3 { 1 } else { cells };
+            origin[axis] = start;
+            widths[axis] = extent / shape[axis] as f32;
+        }
+
+        let mut members = vec![Vec::new(); shape.iter().product()];
+        for (i, (coords, _)) in sites.iter().enumerate() {
+            let mut cell = 0;
+            for axis in 0..3 {
+                let c = ((coords[axis] - origin[axis]) / widths[axis]).floor() as usize;
+                cell = cell * shape[axis] + c.min(shape[axis] - 1);
+            }
+            members[cell].push(i);
+        }
+        Self {
+            shape,
+            periodic: periods.map(|period| period.is_some()),
+            members,
+        }
+    }
+
+    /// the cell itself and all cells next to it
+    fn neighbors(&self, cell: usize) -> Vec<usize> {
+        let position = [
+            cell / (self.shape[1] * self.shape[2]),
+            (cell / self.shape[2]) % self.shape[1],
+            cell % self.shape[2],
+        ];
+        let mut ranges = [Vec::new(), Vec::new(), Vec::new()];
+        for axis in 0..3 {
+            let len = self.shape[axis] as isize;
+            for offset in -1..=1 {
+                let c = position[axis] as isize + offset;
+                if self.periodic[axis] {
+                    ranges[axis].push(c.rem_euclid(len) as usize);
+                } else if (0..len).contains(&c) {
+                    ranges[axis].push(c as usize);
+                }
+            }
+            ranges[axis].sort();
+            ranges[axis].dedup();
+        }
+        let mut out = Vec::new();
+        for x in ranges[0].iter() {
+            for y in ranges[1].iter() {
+                for z in ranges[2].iter() {
+                    out.push((x * self.shape[1] + y) * self.shape[2] + z);
+                }
+            }
+        }
+        out
+    }
+}
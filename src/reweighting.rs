@@ -0,0 +1,343 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{wang_landau::log_sum_exp, Thermodynamics};
+
+/// The most iterations `DensityOfStates::from_histograms` performs.
+const MAX_ITERATIONS: usize = 100_000;
+
+/// A histogram of the internal energies sampled at a fixed beta = 1/(k_B * T).
+/// The bins have the width `bin_width` and are centered at multiples of it,
+/// so for energies which are multiples of the bin width every bin holds one energy.
+#[derive(Debug, Clone)]
+pub struct EnergyHistogram {
+    beta: f32,
+    bin_width: f32,
+    counts: BTreeMap<i64, u64>,
+    samples: u64,
+}
+
+impl EnergyHistogram {
+    pub fn new(beta: f32, bin_width: f32) -> Self {
+        assert!(
+            beta.is_finite() && beta > 0.0,
+            "beta has to be positive and finite"
+        );
+        assert!(bin_width > 0.0);
+        Self {
+            beta,
+            bin_width,
+            counts: BTreeMap::new(),
+            samples: 0,
+        }
+    }
+
+    pub fn add_value(&mut self, energy: f32) {
+        let bin = (energy / self.bin_width).round() as i64;
+        *self.counts.entry(bin).or_insert(0) += 1;
+        self.samples += 1;
+    }
+
+    pub fn beta(&self) -> f32 {
+        self.beta
+    }
+
+    pub fn bin_width(&self) -> f32 {
+        self.bin_width
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// the energy at the center of every visited bin and its count
+    pub fn bins(&self) -> Vec<(f32, u64)> {
+        self.counts
+            .iter()
+            .map(|(bin, count)| (*bin as f32 * self.bin_width, *count))
+            .collect()
+    }
+}
+
+/// The density of states g(E) up to a constant factor, which is estimated by reweighting
+/// energy histograms. The estimate is only reliable for energies which were sampled often,
+/// so thermodynamic quantities should only be calculated between the sampled temperatures.
+#[derive(Debug, Clone)]
+pub struct DensityOfStates {
+    energies: Vec<f32>,
+    ln_g: Vec<f64>,
+}
+
+impl DensityOfStates {
+    /// single histogram reweighting after Ferrenberg and Swendsen,
+    /// ln(g(E)) = ln(H(E)) + beta * E
+    pub fn from_histogram(histogram: &EnergyHistogram) -> Self {
+        let beta = histogram.beta as f64;
+        let (energies, ln_g) = histogram
+            .bins()
+            .into_iter()
+            .map(|(energy, count)| (energy, (count as f64).ln() + beta * energy as f64))
+            .unzip();
+        Self { energies, ln_g }
+    }
+
+    /// This function combines histograms of different temperatures with the weighted histogram
+    /// analysis method (multi histogram reweighting after Ferrenberg and Swendsen).
+    /// The free energies of the histograms minimize a convex function, they are found with
+    /// damped newton steps and the self consistent iteration is only used if a newton step
+    /// doesn't decrease the function.
+    /// They are iterated until none changes by more than `tolerance`, if this doesn't happen
+    /// within `MAX_ITERATIONS` the error holds the last change.
+    /// The samples of the histograms should be about equally correlated.
+    pub fn from_histograms(
+        histograms: &[EnergyHistogram],
+        tolerance: f64,
+    ) -> Result<Self, NotConverged> {
+        assert!(
+            !histograms.is_empty(),
+            "there has to be at least one histogram"
+        );
+        let bin_width = histograms[0].bin_width;
+        assert!(
+            histograms.iter().all(|hist| hist.bin_width == bin_width),
+            "all histograms have to use the same bin width"
+        );
+
+        let mut totals = BTreeMap::new();
+        for histogram in histograms {
+            for (bin, count) in histogram.counts.iter() {
+                *totals.entry(*bin).or_insert(0) += *count;
+            }
+        }
+        let wham = Wham {
+            energies: totals.keys().map(|bin| *bin as f32 * bin_width).collect(),
+            totals: totals.values().map(|count| *count as f64).collect(),
+            betas: histograms.iter().map(|hist| hist.beta as f64).collect(),
+            samples: histograms.iter().map(|hist| hist.samples as f64).collect(),
+        };
+
+        let mut free_energies = vec![0.0; histograms.len()];
+        let mut change = f64::INFINITY;
+        for _ in 0..MAX_ITERATIONS {
+            let new_free_energies = wham
+                .newton_step(&free_energies)
+                .unwrap_or_else(|| wham.self_consistent_step(&free_energies));
+            change = free_energies
+                .iter()
+                .zip(&new_free_energies)
+                .map(|(old, new)| (old - new).abs())
+                .fold(0.0, f64::max);
+            free_energies = new_free_energies;
+            if change < tolerance {
+                return Ok(Self {
+                    ln_g: wham.ln_g(&free_energies),
+                    energies: wham.energies,
+                });
+            }
+        }
+        Err(NotConverged { change })
+    }
+
+    /// the energies of the visited bins
+    pub fn energies(&self) -> &[f32] {
+        &self.energies
+    }
+
+    /// the unnormalized ln(g(E)) of the visited bins
+    pub fn ln_g(&self) -> &[f64] {
+        &self.ln_g
+    }
+
+    /// ln(g(E)) normalized such that the total number of states is exp(ln_states),
+    /// for example `ln_multinomial` of the species counts for a fixed composition.
+    /// This assumes that the histograms cover all energies with a relevant number of states.
+    pub fn normalized_ln_g(&self, ln_states: f64) -> Vec<(f32, f64)> {
+        let ln_tot = log_sum_exp(self.ln_g.iter().copied());
+        self.energies
+            .iter()
+            .zip(&self.ln_g)
+            .map(|(energy, ln_g)| (*energy, ln_g - ln_tot + ln_states))
+            .collect()
+    }
+
+    /// This function calculates the thermodynamic quantities at temp from g(E),
+    /// see `normalized_ln_g` for ln_states.
+    pub fn thermodynamics(&self, temp: f32, ln_states: f64) -> Thermodynamics {
+        Thermodynamics::from_ln_g(temp, &self.normalized_ln_g(ln_states))
+    }
+}
+
+/// The error of `DensityOfStates::from_histograms` if the free energies didn't converge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NotConverged {
+    change: f64,
+}
+
+impl NotConverged {
+    /// the largest change of a free energy in the last iteration
+    pub fn change(&self) -> f64 {
+        self.change
+    }
+}
+
+impl fmt::Display for NotConverged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the free energies didn't converge, the last change was {}",
+            self.change
+        )
+    }
+}
+
+impl std::error::Error for NotConverged {}
+
+/// The combined histograms of `DensityOfStates::from_histograms`.
+/// The free energies beta_k * F_k of the histograms minimize
+/// A(f) = sum_E H(E) ln(sum_k N_k exp(f_k - beta_k * E)) - sum_k N_k f_k,
+/// where H(E) is the total count of E and N_k the samples of histogram k.
+/// Only differences of the free energies matter, so f_0 is kept at 0.
+struct Wham {
+    energies: Vec<f32>,
+    totals: Vec<f64>,
+    betas: Vec<f64>,
+    samples: Vec<f64>,
+}
+
+impl Wham {
+    /// ln(sum_k N_k exp(f_k - beta_k * E)) for every energy
+    fn ln_denominators(&self, free_energies: &[f64]) -> Vec<f64> {
+        self.energies
+            .iter()
+            .map(|energy| {
+                log_sum_exp(
+                    self.betas
+                        .iter()
+                        .zip(&self.samples)
+                        .zip(free_energies)
+                        .map(|((beta, n), f)| n.ln() + f - beta * *energy as f64),
+                )
+            })
+            .collect()
+    }
+
+    fn ln_g(&self, free_energies: &[f64]) -> Vec<f64> {
+        self.totals
+            .iter()
+            .zip(self.ln_denominators(free_energies))
+            .map(|(total, ln_denominator)| total.ln() - ln_denominator)
+            .collect()
+    }
+
+    fn objective(&self, free_energies: &[f64]) -> f64 {
+        self.totals
+            .iter()
+            .zip(self.ln_denominators(free_energies))
+            .map(|(total, ln_denominator)| total * ln_denominator)
+            .sum::<f64>()
+            - self
+                .samples
+                .iter()
+                .zip(free_energies)
+                .map(|(n, f)| n * f)
+                .sum::<f64>()
+    }
+
+    /// f_k = -ln(sum_E g(E) exp(-beta_k * E))
+    fn self_consistent_step(&self, free_energies: &[f64]) -> Vec<f64> {
+        let ln_g = self.ln_g(free_energies);
+        let mut new_free_energies: Vec<f64> = self
+            .betas
+            .iter()
+            .map(|beta| {
+                -log_sum_exp(
+                    ln_g.iter()
+                        .zip(&self.energies)
+                        .map(|(ln_g, energy)| ln_g - beta * *energy as f64),
+                )
+            })
+            .collect();
+        let offset = new_free_energies[0];
+        new_free_energies.iter_mut().for_each(|f| *f -= offset);
+        new_free_energies
+    }
+
+    /// This function returns the free energies after a newton step which is halved until
+    /// it decreases the objective, or None if there is no such step.
+    fn newton_step(&self, free_energies: &[f64]) -> Option<Vec<f64>> {
+        let k = free_energies.len();
+        if k < 2 {
+            return None;
+        }
+        // the gradient and hessian of the objective for f_1 to f_(k-1)
+        let mut gradient: Vec<f64> = self.samples[1..].iter().map(|n| -n).collect();
+        let mut hessian = vec![vec![0.0; k - 1]; k - 1];
+        let ln_denominators = self.ln_denominators(free_energies);
+        let mut weights = vec![0.0; k];
+        for ((energy, total), ln_denominator) in
+            self.energies.iter().zip(&self.totals).zip(&ln_denominators)
+        {
+            for (i, weight) in weights.iter_mut().enumerate() {
+                *weight = (self.samples[i].ln() + free_energies[i]
+                    - self.betas[i] * *energy as f64
+                    - ln_denominator)
+                    .exp();
+            }
+            for i in 1..k {
+                gradient[i - 1] += total * weights[i];
+                hessian[i - 1][i - 1] += total * weights[i];
+                for j in 1..k {
+                    hessian[i - 1][j - 1] -= total * weights[i] * weights[j];
+                }
+            }
+        }
+        let step = solve(hessian, gradient.iter().map(|g| -g).collect())?;
+        let slope = gradient.iter().zip(&step).map(|(g, s)| g * s).sum::<f64>();
+        if slope.is_nan() || slope >= 0.0 {
+            return None;
+        }
+
+        let objective = self.objective(free_energies);
+        let mut factor = 1.0;
+        while factor > 1e-10 {
+            let mut new_free_energies = free_energies.to_vec();
+            for (f, s) in new_free_energies[1..].iter_mut().zip(&step) {
+                *f += factor * s;
+            }
+            if self.objective(&new_free_energies) <= objective + 1e-4 * factor * slope {
+                return Some(new_free_energies);
+            }
+            factor /= 2.0;
+        }
+        None
+    }
+}
+
+/// This function solves the linear system `matrix` * x = `rhs` with gaussian elimination,
+/// or returns None if the matrix is singular.
+fn solve(mut matrix: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+    let n = rhs.len();
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|a, b| matrix[*a][col].abs().total_cmp(&matrix[*b][col].abs()))?;
+        if matrix[pivot][col] == 0.0 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        rhs.swap(col, pivot);
+        let (pivot_rows, rows) = matrix.split_at_mut(col + 1);
+        let pivot_row = &pivot_rows[col];
+        for (i, row) in rows.iter_mut().enumerate() {
+            let factor = row[col] / pivot_row[col];
+            for (value, pivot_value) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+            rhs[col + 1 + i] -= factor * rhs[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum = (row + 1..n).map(|k| matrix[row][k] * x[k]).sum::<f64>();
+        x[row] = (rhs[row] - sum) / matrix[row][row];
+    }
+    Some(x)
+}
@@ -0,0 +1,153 @@
+/// An observer `(interval, callback)` of `System::anneal`, the callback is called with the
+/// system, the step and the temperature.
+pub type Observer<'a, S> = (usize, &'a mut dyn FnMut(&mut S, usize, f32));
+
+/// A temperature for every step of an annealing run, see `System::anneal`.
+pub trait TemperatureSchedule {
+    /// the number of steps of the run
+    fn steps(&self) -> usize;
+    /// the temperature at `step`
+    fn temp(&self, step: usize) -> f32;
+}
+
+/// The temperature changes linearly from `start` to `end`.
+/// `end` is reached after the last step, at `step == steps`, which isn't run by `System::anneal`.
+#[derive(Debug, Clone, Copy)]
+pub struct Linear {
+    start: f32,
+    end: f32,
+    steps: usize,
+}
+
+impl Linear {
+    pub fn new(start: f32, end: f32, steps: usize) -> Self {
+        Self { start, end, steps }
+    }
+}
+
+impl TemperatureSchedule for Linear {
+    fn steps(&self) -> usize {
+        self.steps
+    }
+
+    fn temp(&self, step: usize) -> f32 {
+        self.start + (self.end - self.start) / self.steps as f32 * step as f32
+    }
+}
+
+/// The temperature decays exponentially from `start` to `end`.
+/// `end` is reached after the last step, at `step == steps`, which isn't run by `System::anneal`.
+#[derive(Debug, Clone, Copy)]
+pub struct Exponential {
+    start: f32,
+    end: f32,
+    steps: usize,
+}
+
+impl Exponential {
+    pub fn new(start: f32, end: f32, steps: usize) -> Self {
+        assert!(
+            start > 0.0 && end > 0.0,
+            "the temperatures have to be positive"
+        );
+        Self { start, end, steps }
+    }
+}
+
+impl TemperatureSchedule for Exponential {
+    fn steps(&self) -> usize {
+        self.steps
+    }
+
+    fn temp(&self, step: usize) -> f32 {
+        self.start * ((self.end / self.start).ln() / self.steps as f32 * step as f32).exp()
+    }
+}
+
+/// The temperature follows start / (1 + a * ln(1 + step)),
+/// where a is chosen such that the temperature reaches `end` after the last step,
+/// at `step == steps`, which isn't run by `System::anneal`.
+#[derive(Debug, Clone, Copy)]
+pub struct Logarithmic {
+    start: f32,
+    a: f32,
+    steps: usize,
+}
+
+impl Logarithmic {
+    pub fn new(start: f32, end: f32, steps: usize) -> Self {
+        assert!(
+            start > 0.0 && end > 0.0,
+            "the temperatures have to be positive"
+        );
+        Self {
+            start,
+            a: (start / end - 1.0) / (1.0 + steps as f32).ln(),
+            steps,
+        }
+    }
+}
+
+impl TemperatureSchedule for Logarithmic {
+    fn steps(&self) -> usize {
+        self.steps
+    }
+
+    fn temp(&self, step: usize) -> f32 {
+        self.start / (1.0 + self.a * (1.0 + step as f32).ln())
+    }
+}
+
+/// Every temperature of `temps` is held for `steps_per_temp` steps.
+#[derive(Debug, Clone)]
+pub struct StepwiseHold {
+    temps: Vec<f32>,
+    steps_per_temp: usize,
+}
+
+impl StepwiseHold {
+    pub fn new(temps: Vec<f32>, steps_per_temp: usize) -> Self {
+        assert!(
+            !temps.is_empty(),
+            "there has to be at least one temperature"
+        );
+        assert!(steps_per_temp > 0);
+        Self {
+            temps,
+            steps_per_temp,
+        }
+    }
+}
+
+impl TemperatureSchedule for StepwiseHold {
+    fn steps(&self) -> usize {
+        self.temps.len() * self.steps_per_temp
+    }
+
+    fn temp(&self, step: usize) -> f32 {
+        self.temps[(step / self.steps_per_temp).min(self.temps.len() - 1)]
+    }
+}
+
+/// The temperature is given by a function of the step.
+#[derive(Debug, Clone, Copy)]
+pub struct Custom<F> {
+    func: F,
+    steps: usize,
+}
+
+impl<F: Fn(usize) -> f32> Custom<F> {
+    pub fn new(steps: usize, func: F) -> Self {
+        Self { func, steps }
+    }
+}
+
+impl<F: Fn(usize) -> f32> TemperatureSchedule for Custom<F> {
+    fn steps(&self) -> usize {
+        self.steps
+    }
+
+    fn temp(&self, step: usize) -> f32 {
+        (self.func)(step)
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{prefixed, Lattice, RandAtom};
+
+/// The Warren-Cowley short range order parameters alpha_ij = 1 - P(j | i) / c_j of every
+/// neighbor shell and every pair of species i <= j, where P(j | i) is the probability that
+/// a neighbor of an i atom is a j atom and c_j is the concentration of j.
+/// alpha_ij is 0 for a random alloy, negative if i and j prefer to be neighbors and positive
+/// if they avoid each other.
+/// Vacancies are ignored, bonds to walls are counted like bonds between sites.
+#[derive(Debug, Clone)]
+pub struct ShortRangeOrder<A> {
+    species: Vec<A>,
+    /// the parameters of every shell in the order of `pairs`
+    alphas: Vec<Vec<f32>>,
+}
+
+impl<A: RandAtom + Copy> ShortRangeOrder<A> {
+    /// This function calculates the parameters of all shells of the lattice from the pair
+    /// counts of `all_shell_neighbors`.
+    pub fn new<L: Lattice<Atom = A>>(lattice: &L) -> Self {
+        let species = A::all_atoms();
+        let n = species.len();
+        let mut atom_counts = vec![0; n];
+        for atom in lattice.as_flat_slice() {
+            if let Some(i) = species.iter().position(|species| species == atom) {
+                atom_counts[i] += 1;
+            }
+        }
+        let tot_atoms = atom_counts.iter().sum::<u64>() as f64;
+
+        let alphas = (0..lattice.shells())
+            .map(|shell| {
+                // every bond counted in both directions
+                let mut pair_counts = vec![vec![0; n]; n];
+                for ((a_1, a_2), count) in lattice.all_shell_neighbors(shell) {
+                    let i = species.iter().position(|species| *species == a_1);
+                    let j = species.iter().position(|species| *species == a_2);
+                    if let (Some(i), Some(j)) = (i, j) {
+                        pair_counts[i][j] += count as u64;
+                        pair_counts[j][i] += count as u64;
+                    }
+                }
+                Self::pairs(n)
+                    .map(|(i, j)| {
+                        let neighbors_of_i = pair_counts[i].iter().sum::<u64>();
+                        if neighbors_of_i == 0 || atom_counts[j] == 0 {
+                            return f32::NAN;
+                        }
+                        let p_j_given_i = pair_counts[i][j] as f64 / neighbors_of_i as f64;
+                        (1.0 - p_j_given_i / (atom_counts[j] as f64 / tot_atoms)) as f32
+                    })
+                    .collect()
+            })
+            .collect();
+        Self { species, alphas }
+    }
+
+    /// all pairs of species i <= j in the order of the parameters
+    fn pairs(species: usize) -> impl Iterator<Item = (usize, usize)> {
+        (0..species).flat_map(move |i| (i..species).map(move |j| (i, j)))
+    }
+
+    pub fn shells(&self) -> usize {
+        self.alphas.len()
+    }
+
+    /// the parameter of the pair of atoms in `shell`, alpha_ij = alpha_ji
+    pub fn alpha(&self, shell: usize, a_1: A, a_2: A) -> f32 {
+        let i = self.position(a_1);
+        let j = self.position(a_2);
+        let (i, j) = (i.min(j), i.max(j));
+        let pos = Self::pairs(self.species.len())
+            .position(|pair| pair == (i, j))
+            .expect("the pair exists");
+        self.alphas[shell][pos]
+    }
+
+    fn position(&self, atom: A) -> usize {
+        self.species
+            .iter()
+            .position(|species| *species == atom)
+            .expect("the atom is a species of the lattice")
+    }
+
+    /// The categories are "shell s alpha i-j" for every shell and pair of species,
+    /// where i and j are the values of the atoms.
+    pub fn get_categories(prefix: Option<impl ToString>, shells: usize) -> Vec<String> {
+        let species = A::all_atoms();
+        let mut out = Vec::new();
+        for shell in 0..shells {
+            for (i, j) in Self::pairs(species.len()) {
+                out.push(format!(
+                    "shell {} alpha {}-{}",
+                    shell, *species[i], *species[j]
+                ));
+            }
+        }
+        prefixed(prefix, out)
+    }
+
+    pub fn as_vec_f32(&self) -> Vec<f32> {
+        self.alphas.iter().flatten().copied().collect()
+    }
+}
@@ -0,0 +1,268 @@
+use crate::prefixed;
+
+/// The number of bins kept for the jackknife, when there are twice as many bins
+/// neighboring bins are merged.
+const BINS: usize = 64;
+/// The least number of blocks of a blocking level to be used for the error.
+const MIN_BLOCKS: u64 = 32;
+
+pub struct StreamingStats {
+    count: u32,
+    m_k: f64,
+    m_k_1: f64,
+    v_k: f64,
+    v_k_1: f64,
+    /// all sums are taken of the values minus the first value, this keeps them small
+    shift: f64,
+    /// level i of the blocking analysis has blocks of 2^i values
+    levels: Vec<BlockingLevel>,
+    /// the sums of the values and their squares in the full bins of the jackknife
+    bins: Vec<(f64, f64)>,
+    bin_size: u32,
+    current_bin: (f64, f64, u32),
+}
+
+#[derive(Clone, Copy, Default)]
+struct BlockingLevel {
+    count: u64,
+    sum: f64,
+    sum_sq: f64,
+    pending: Option<f64>,
+}
+
+impl BlockingLevel {
+    fn error(&self) -> f64 {
+        let n = self.count as f64;
+        let mean = self.sum / n;
+        let variance = (self.sum_sq / n - mean * mean).max(0.0);
+        (variance / (n - 1.0)).sqrt()
+    }
+}
+
+impl StreamingStats {
+    // variance after https://math.stackexchange.com/questions/20593/calculate-variance-from-a-stream-of-sample-values
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            m_k: 0.0,
+            m_k_1: 0.0,
+            v_k: 0.0,
+            v_k_1: 0.0,
+            shift: 0.0,
+            levels: Vec::new(),
+            bins: Vec::new(),
+            bin_size: 1,
+            current_bin: (0.0, 0.0, 0),
+        }
+    }
+    // _1 is old
+    pub fn add_value(&mut self, x_k: f32) {
+        let x_k = x_k as f64;
+        self.count += 1;
+        self.m_k_1 = self.m_k;
+        self.v_k_1 = self.v_k;
+        self.m_k = self.m_k_1 + (x_k - self.m_k_1) / self.count as f64;
+        self.v_k = self.v_k_1 + (x_k - self.m_k_1) * (x_k - self.m_k);
+
+        if self.count == 1 {
+            self.shift = x_k;
+        }
+        let x = x_k - self.shift;
+        self.add_block(0, x);
+        self.add_to_bin(x);
+    }
+
+    pub fn avg(&self) -> f32 {
+        self.m_k as f32
+    }
+
+    pub fn variance(&self) -> f32 {
+        (self.v_k / self.count as f64) as f32
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    fn add_block(&mut self, level: usize, x: f64) {
+        if self.levels.len() == level {
+            self.levels.push(BlockingLevel::default());
+        }
+        let block = &mut self.levels[level];
+        block.count += 1;
+        block.sum += x;
+        block.sum_sq += x * x;
+        match block.pending.take() {
+            Some(other) => self.add_block(level + 1, (x + other) / 2.0),
+            None => block.pending = Some(x),
+        }
+    }
+
+    fn add_to_bin(&mut self, x: f64) {
+        self.current_bin.0 += x;
+        self.current_bin.1 += x * x;
+        self.current_bin.2 += 1;
+        if self.current_bin.2 == self.bin_size {
+            self.bins.push((self.current_bin.0, self.current_bin.1));
+            self.current_bin = (0.0, 0.0, 0);
+            if self.bins.len() == 2 * BINS {
+                self.bins = self
+                    .bins
+                    .chunks(2)
+                    .map(|pair| (pair[0].0 + pair[1].0, pair[0].1 + pair[1].1))
+                    .collect();
+                self.bin_size *= 2;
+            }
+        }
+    }
+
+    /// the standard error of the mean of every blocking level,
+    /// at level i the values are averaged in blocks of 2^i
+    pub fn blocking_errors(&self) -> Vec<f32> {
+        self.levels
+            .iter()
+            .take_while(|level| level.count > 1)
+            .map(|level| level.error() as f32)
+            .collect()
+    }
+
+    /// The standard error of the mean, which takes the correlation of the values into account.
+    /// This is the largest error of the blocking levels with enough blocks,
+    /// it is only reliable if the errors reach a plateau, see `blocking_errors`.
+    pub fn error(&self) -> f32 {
+        self.levels
+            .iter()
+            .filter(|level| level.count >= MIN_BLOCKS)
+            .map(|level| level.error())
+            .fold(0.0, f64::max) as f32
+    }
+
+    /// The integrated autocorrelation time tau_int in number of values,
+    /// estimated by the blocking analysis from error^2 = (1 + 2 * tau_int) * variance / count.
+    /// This is 0 for uncorrelated values.
+    pub fn autocorrelation_time(&self) -> f32 {
+        match self.levels.first() {
+            Some(level) if level.count >= MIN_BLOCKS && level.error() > 0.0 => {
+                let g = (self.error() as f64 / level.error()).powi(2);
+                ((g - 1.0) / 2.0).max(0.0) as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// This function calculates `func` of the mean of the values and the mean of their squares
+    /// and its error with the jackknife over the bins.
+    /// It returns the bias corrected estimate and its error.
+    pub fn jackknife(&self, func: impl Fn(f64, f64) -> f64) -> (f32, f32) {
+        let n_bins = self.bins.len();
+        if n_bins < 2 {
+            return (f32::NAN, f32::NAN);
+        }
+        let shift = self.shift;
+        // the function of the unshifted means
+        let func = |mean: f64, mean_sq: f64| {
+            func(mean + shift, mean_sq + 2.0 * shift * mean + shift * shift)
+        };
+        let (sum, sum_sq) = self
+            .bins
+            .iter()
+            .fold((0.0, 0.0), |acc, bin| (acc.0 + bin.0, acc.1 + bin.1));
+        let count = (n_bins * self.bin_size as usize) as f64;
+        let all = func(sum / count, sum_sq / count);
+        let leave_one_out: Vec<f64> = self
+            .bins
+            .iter()
+            .map(|bin| {
+                let count = count - self.bin_size as f64;
+                func((sum - bin.0) / count, (sum_sq - bin.1) / count)
+            })
+            .collect();
+        let n = n_bins as f64;
+        let mean = leave_one_out.iter().sum::<f64>() / n;
+        let variance = leave_one_out
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            * (n - 1.0)
+            / n;
+        ((n * all - (n - 1.0) * mean) as f32, variance.sqrt() as f32)
+    }
+
+    /// the variance of the values and its error from the jackknife,
+    /// for example for the heat capacity variance(E) / T^2
+    pub fn jackknife_variance(&self) -> (f32, f32) {
+        self.jackknife(|mean, mean_sq| mean_sq - mean * mean)
+    }
+
+    pub fn summary(&self) -> StatsSummary {
+        let (variance, variance_error) = self.jackknife_variance();
+        StatsSummary {
+            mean: self.avg(),
+            error: self.error(),
+            autocorrelation_time: self.autocorrelation_time(),
+            variance,
+            variance_error,
+        }
+    }
+}
+
+impl Default for StreamingStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The results of `StreamingStats` with error bars, which can be logged as a row.
+#[derive(Debug, Clone, Copy)]
+pub struct StatsSummary {
+    mean: f32,
+    error: f32,
+    autocorrelation_time: f32,
+    variance: f32,
+    variance_error: f32,
+}
+
+impl StatsSummary {
+    pub fn mean(&self) -> f32 {
+        self.mean
+    }
+
+    pub fn error(&self) -> f32 {
+        self.error
+    }
+
+    pub fn autocorrelation_time(&self) -> f32 {
+        self.autocorrelation_time
+    }
+
+    pub fn variance(&self) -> f32 {
+        self.variance
+    }
+
+    pub fn variance_error(&self) -> f32 {
+        self.variance_error
+    }
+
+    pub fn get_categories(prefix: Option<impl ToString>) -> Vec<String> {
+        prefixed(
+            prefix,
+            vec![
+                "mean".to_owned(),
+                "error".to_owned(),
+                "autocorrelation time".to_owned(),
+                "variance".to_owned(),
+                "variance error".to_owned(),
+            ],
+        )
+    }
+
+    pub fn as_vec_f32(&self) -> Vec<f32> {
+        vec![
+            self.mean,
+            self.error,
+            self.autocorrelation_time,
+            self.variance,
+            self.variance_error,
+        ]
+    }
+}
@@ -0,0 +1,106 @@
+use std::f64::consts::PI;
+
+use crate::{
+    fft::{fft_nd, Complex},
+    Grid,
+};
+
+/// The static structure factor S(k) = |sum_r (n(r) - c) exp(-i k r)|^2 / N of the occupation
+/// n(r) of one species with the concentration c, calculated with a fast fourier transform.
+/// The lattice is treated as periodic along every axis.
+/// The wave vectors are k_i = 2 pi m_i / L_i for the side lengths L_i of the grid,
+/// m_i above L_i / 2 stand for the negative m_i - L_i.
+#[derive(Debug, Clone)]
+pub struct StructureFactor {
+    shape: Vec<usize>,
+    values: Vec<f32>,
+}
+
+impl StructureFactor {
+    pub fn new<L: Grid>(lattice: &L, atom: L::Atom) -> Self {
+        let shape = lattice.grid_shape();
+        let sites = lattice.as_flat_slice();
+        let concentration =
+            sites.iter().filter(|site| **site == atom).count() as f64 / sites.len() as f64;
+        let mut data: Vec<Complex> = sites
+            .iter()
+            .map(|site| {
+                let occupation = if *site == atom { 1.0 } else { 0.0 };
+                Complex::new(occupation - concentration, 0.0)
+            })
+            .collect();
+        fft_nd(&mut data, &shape);
+        let values = data
+            .iter()
+            .map(|value| (value.norm_sqr() / sites.len() as f64) as f32)
+            .collect();
+        Self { shape, values }
+    }
+
+    /// the side lengths of the grid from the slowest to the fastest varying axis
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// S(k) of every wave vector in the order of `as_flat_slice`
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    /// the length of the wave vector at position `flat` of `values`
+    pub fn wave_number(&self, mut flat: usize) -> f32 {
+        let mut k_squared = 0.0;
+        for len in self.shape.iter().rev() {
+            let m = flat % len;
+            let m = if m > len / 2 {
+                m as f64 - *len as f64
+            } else {
+                m as f64
+            };
+            k_squared += (2.0 * PI * m / *len as f64).powi(2);
+            flat /= len;
+        }
+        k_squared.sqrt() as f32
+    }
+
+    /// This function averages S(k) over shells of |k| with the width 2 pi / L_max
+    /// and returns the center of every shell which contains a wave vector and its average.
+    /// k = 0 is left out.
+    pub fn spherical_average(&self) -> Vec<(f32, f32)> {
+        let width = 2.0 * PI as f32 / *self.shape.iter().max().expect("the grid has axes") as f32;
+        let mut shells: Vec<(f64, u32)> = Vec::new();
+        for (flat, value) in self.values.iter().enumerate().skip(1) {
+            let shell = (self.wave_number(flat) / width).round() as usize;
+            if shells.len() <= shell {
+                shells.resize(shell + 1, (0.0, 0));
+            }
+            shells[shell].0 += *value as f64;
+            shells[shell].1 += 1;
+        }
+        shells
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (_, count))| *count > 0)
+            .map(|(shell, (sum, count))| (shell as f32 * width, (sum / count as f64) as f32))
+            .collect()
+    }
+
+    /// the first moment <k> = sum k S(k) / sum S(k) of the wave vectors with 0 < |k| <= pi
+    pub fn first_moment(&self) -> f32 {
+        let mut weighted = 0.0;
+        let mut tot = 0.0;
+        for (flat, value) in self.values.iter().enumerate().skip(1) {
+            let k = self.wave_number(flat) as f64;
+            if k <= PI {
+                weighted += k * *value as f64;
+                tot += *value as f64;
+            }
+        }
+        (weighted / tot) as f32
+    }
+
+    /// the characteristic domain size L = 2 pi / <k>, see `first_moment`
+    pub fn domain_size(&self) -> f32 {
+        2.0 * PI as f32 / self.first_moment()
+    }
+}
@@ -1,8 +1,12 @@
-use rand::{seq::SliceRandom, Rng, SeedableRng};
+use std::fmt;
+
+use rand::{distributions::Distribution, seq::SliceRandom, Rng, SeedableRng};
 use rand_seeder::Seeder;
 
 use crate::{
-    ClusterCounter, ClusterDistribution, Energies, GifFrame, Lattice, Mark, MyRng, RandAtom,
+    detect_equilibration, AcceptanceRule, BinAtom, ClusterCounter, ClusterDistribution, Energies,
+    EnergyHistogram, Geometry, GifFrame, Grid, Lattice, Mark, MyRng, Observer, OrderParameter,
+    PairCorrelation, RandAtom, ShortRangeOrder, StructureFactor, TemperatureSchedule,
 };
 
 pub struct System<L: Lattice, E: Energies<L::Atom>> {
@@ -10,15 +14,50 @@ pub struct System<L: Lattice, E: Energies<L::Atom>> {
     lattice: L,
     rng: MyRng,
     internal_energy: Option<f32>,
-    vacancy: Option<L::Index>,
+    vacancies: Vec<L::Index>,
+    next_vacancy: usize,
+    species: Vec<L::Atom>,
+    time: f64,
+    acceptance_rule: AcceptanceRule,
+    /// the flat indexes of the sites of every species in the order of `species`
+    species_sites: Vec<Vec<usize>>,
+    /// the position of every site in its list in `species_sites`
+    site_positions: Vec<usize>,
+    /// all indexes ordered by their flat index
+    idxs: Vec<L::Index>,
+}
+
+/// The error of `System::monte_carlo_swap` if there are no two atoms of different species.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoUnlikePairs;
+
+impl fmt::Display for NoUnlikePairs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "there are no two atoms of different species to swap")
+    }
 }
 
+impl std::error::Error for NoUnlikePairs {}
+
 /// all constructors
 impl<L: Lattice, E: Energies<L::Atom>> System<L, E> {
     pub fn new(
         bond_energies: E,
         seed: Option<&str>,
         concentration: <L::Atom as RandAtom>::Concentration,
+    ) -> Self
+    where
+        L::Shape: Default,
+    {
+        Self::with_shape(bond_energies, seed, concentration, Default::default())
+    }
+
+    /// Like `new` but for lattices which need a shape, for example their size, at runtime.
+    pub fn with_shape(
+        bond_energies: E,
+        seed: Option<&str>,
+        concentration: <L::Atom as RandAtom>::Concentration,
+        shape: L::Shape,
     ) -> Self {
         let mut rng =
             match seed {
@@ -27,20 +66,75 @@ impl<L: Lattice, E: Energies<L::Atom>> System<L, E> {
             };
 
         let grid = L::fill_with_fn(
+            shape,
             &mut |_| <L::Atom as RandAtom>::with_concentration(&mut rng, concentration)
         );
 
+        assert!(
+            bond_energies.shells() <= grid.shells(),
+            "the lattice doesn't provide enough neighbor shells for the energies"
+        );
+        let mut idxs = grid.all_idxs();
+        idxs.sort_by_key(|idx| grid.flat_index(*idx));
+        let vacancies = idxs
+            .iter()
+            .copied()
+            .filter(|idx| grid[*idx].is_vacancy())
+            .collect();
+        let species = L::Atom::all_atoms();
+        let mut species_sites = vec![Vec::new(); species.len()];
+        let mut site_positions = vec![0; idxs.len()];
+        for (flat, atom) in grid.as_flat_slice().iter().enumerate() {
+            if let Some(i) = species.iter().position(|species| species == atom) {
+                site_positions[flat] = species_sites[i].len();
+                species_sites[i].push(flat);
+            }
+        }
+
         let mut obj = Self {
             bond_energies,
             lattice: grid,
             rng,
             internal_energy: None,
-            vacancy: None,
+            vacancies,
+            next_vacancy: 0,
+            species,
+            time: 0.0,
+            acceptance_rule: AcceptanceRule::Metropolis,
+            species_sites,
+            site_positions,
+            idxs,
         };
         obj.internal_energy();
         obj
     }
 
+    /// This function places `count` additional vacancies at random sites.
+    pub fn with_vacancies(mut self, count: usize) -> Self {
+        assert!(
+            self.vacancies.len() + count < self.tot_sites(),
+            "not enough sites for vacancies"
+        );
+        for _ in 0..count {
+            self.add_vacancy();
+        }
+        self
+    }
+
+    /// sets the rule by which all moves are accepted, the default is `AcceptanceRule::Metropolis`
+    pub fn with_acceptance_rule(mut self, rule: AcceptanceRule) -> Self {
+        self.acceptance_rule = rule;
+        self
+    }
+
+    pub fn set_acceptance_rule(&mut self, rule: AcceptanceRule) {
+        self.acceptance_rule = rule;
+    }
+
+    pub fn acceptance_rule(&self) -> &AcceptanceRule {
+        &self.acceptance_rule
+    }
+
     pub fn tot_sites(&self) -> usize {
         self.lattice.tot_sites()
     }
@@ -48,6 +142,19 @@ impl<L: Lattice, E: Energies<L::Atom>> System<L, E> {
     pub fn get_energies_dict(&self) -> String {
         self.bond_energies.as_dict()
     }
+
+    pub fn lattice(&self) -> &L {
+        &self.lattice
+    }
+
+    pub fn vacancies(&self) -> &[L::Index] {
+        &self.vacancies
+    }
+
+    /// This function returns the number of atoms of each species in the order of `all_atoms`.
+    pub fn species_counts(&self) -> Vec<usize> {
+        self.species_sites.iter().map(|sites| sites.len()).collect()
+    }
 }
 
 /// everything energies
@@ -58,30 +165,61 @@ impl<L: Lattice, E: Energies<L::Atom>> System<L, E> {
         if let Some(energy) = self.internal_energy {
             energy
         } else {
-            let energy =
-                self.lattice
-                    .all_neighbors()
-                    .iter()
-                    .fold(0.0, |energy, ((a1, a2), count)| {
-                        energy + self.bond_energies.get_interaction_energy(*a1, *a2) * *count as f32
-                    });
+            let mut energy = 0.0;
+            for shell in 0..self.bond_energies.shells() {
+                for ((a1, a2), count) in self.lattice.all_shell_neighbors(shell) {
+                    energy += self
+                        .bond_energies
+                        .get_shell_interaction_energy(shell, a1, a2)
+                        * count as f32;
+                }
+            }
             self.internal_energy = Some(energy);
             energy
         }
     }
 
+    /// This function discards the incrementally updated energy and recalculates it
+    /// from the whole lattice.
+    pub fn recalculate_internal_energy(&mut self) -> f32 {
+        self.internal_energy = None;
+        self.internal_energy()
+    }
+
     /// This function returns the local energy around the idx if it was swapped to atom_at_idx
     fn energies_around(&self, idx: L::Index) -> f32 {
-        self.lattice
-            .all_neighbors_to(idx)
-            .as_ref()
-            .iter()
-            .fold(0.0, |energy, idx_i| {
-                energy
-                    + self
-                        .bond_energies
-                        .get_interaction_energy(self.lattice[idx], self.lattice[*idx_i])
-            })
+        let mut energy =
+            self.lattice
+                .all_neighbors_to(idx)
+                .as_ref()
+                .iter()
+                .fold(0.0, |energy, idx_i| {
+                    energy
+                        + self.bond_energies.get_shell_interaction_energy(
+                            0,
+                            self.lattice[idx],
+                            self.lattice[*idx_i],
+                        )
+                });
+        for wall in self.lattice.wall_neighbors_to(idx) {
+            energy += self
+                .bond_energies
+                .get_shell_interaction_energy(0, self.lattice[idx], wall);
+        }
+        // the nearest neighbors are handled separately because this avoids an allocation
+        (1..self.bond_energies.shells()).fold(energy, |energy, shell| {
+            self.lattice
+                .shell_neighbors_to(idx, shell)
+                .iter()
+                .fold(energy, |energy, idx_i| {
+                    energy
+                        + self.bond_energies.get_shell_interaction_energy(
+                            shell,
+                            self.lattice[idx],
+                            self.lattice[*idx_i],
+                        )
+                })
+        })
     }
 
     /// This function updates the energy if already calculated and recalculates the whole energy
@@ -99,61 +237,660 @@ impl<L: Lattice, E: Energies<L::Atom>> System<L, E> {
 /// all swapping processes
 impl<L: Lattice, E: Energies<L::Atom>> System<L, E> {
     /// This function performs a monte carlo swap with the boltzman factor beta = 1/(k_B * T)
-    pub fn monte_carlo_swap(&mut self, beta: f32) -> bool {
-        let (idx_1, idx_2) = loop {
-            let (idx_1, idx_2) = self.lattice.choose_idxs_uniformly(&mut self.rng);
-            if self.lattice[idx_1] != self.lattice[idx_2] {
-                break (idx_1, idx_2);
-            }
-        };
+    /// The swap is accepted with the acceptance rule of the system.
+    /// Vacancies are never swapped by this function, they only move with `move_vacancy`.
+    /// The pair is drawn directly from the pairs of atoms of different species, if there is no
+    /// such pair an error is returned.
+    pub fn monte_carlo_swap(&mut self, beta: f32) -> Result<bool, NoUnlikePairs> {
+        self.monte_carlo_swap_with(|system, delta_e| system.is_accepted(beta, delta_e))
+    }
+
+    /// Like `monte_carlo_swap` but the swap is accepted if `accept` returns true for the
+    /// change of energy. `accept` is called before the energy of the system is updated.
+    pub(crate) fn monte_carlo_swap_with(
+        &mut self,
+        accept: impl FnOnce(&mut Self, f32) -> bool,
+    ) -> Result<bool, NoUnlikePairs> {
+        let (idx_1, idx_2) = self.choose_unlike_pair()?;
+        Ok(self.swap_pair_with(idx_1, idx_2, accept))
+    }
+
+    /// This function swaps a random atom with one of its nearest neighbors (local kawasaki
+    /// dynamics), which is closer to diffusion than `monte_carlo_swap`.
+    /// Pairs of the same species or with a vacancy are rejected, so the choice of the pairs
+    /// doesn't depend on the configuration.
+    pub fn local_swap(&mut self, beta: f32) -> bool {
+        let idx = self.lattice.random_idx(&mut self.rng);
+        let other_idx = *self
+            .lattice
+            .all_neighbors_to(idx)
+            .as_ref()
+            .choose(&mut self.rng)
+            .expect("all atoms have neighbors");
+        self.swap_pair_with(idx, other_idx, |system, delta_e| {
+            system.is_accepted(beta, delta_e)
+        })
+    }
+
+    /// This function swaps a random atom with the atom displaced by an offset drawn from
+    /// `distr`, see `Lattice::choose_idxs_with_distribution`.
+    /// The distribution has to be symmetric, the offsets o and -o have to be equally likely.
+    /// Pairs of the same species or with a vacancy are rejected.
+    pub fn swap_with_distribution(
+        &mut self,
+        beta: f32,
+        distr: impl Distribution<L::Index>,
+    ) -> bool {
+        let (idx_1, idx_2) = self
+            .lattice
+            .choose_idxs_with_distribution(&mut self.rng, distr);
+        self.swap_pair_with(idx_1, idx_2, |system, delta_e| {
+            system.is_accepted(beta, delta_e)
+        })
+    }
+
+    /// This function swaps the atoms at idx_1 and idx_2 if `accept` returns true for the change
+    /// of energy. Pairs of the same species or with a vacancy are always rejected.
+    fn swap_pair_with(
+        &mut self,
+        idx_1: L::Index,
+        idx_2: L::Index,
+        accept: impl FnOnce(&mut Self, f32) -> bool,
+    ) -> bool {
+        if self.lattice[idx_1] == self.lattice[idx_2]
+            || self.lattice[idx_1].is_vacancy()
+            || self.lattice[idx_2].is_vacancy()
+        {
+            return false;
+        }
         let e_0 = self.energies_around(idx_1) + self.energies_around(idx_2);
-        self.lattice.swap_vals(idx_1, idx_2);
+        self.swap_sites(idx_1, idx_2);
         let e_1 = self.energies_around(idx_1) + self.energies_around(idx_2);
         let delta_e = e_1 - e_0;
-        if delta_e <= 0.0 || (self.rng.gen::<f32>() < (-beta * delta_e).exp()) {
+        if accept(self, delta_e) {
             self.update_energy(delta_e);
             true
         } else {
-            self.lattice.swap_vals(idx_1, idx_2);
+            self.swap_sites(idx_1, idx_2);
             false
         }
     }
 
-    /// this function does not put any other values in to the vacancy spot.
-    /// it just reinterprets this spot as being empty thus having bond energies = 0
+    /// This function moves a randomly chosen vacancy to a random neighboring site.
+    /// If there is no vacancy in the system yet, one is placed at a random site.
     pub fn move_vacancy(&mut self, beta: f32) -> bool {
-        if let Some(idx) = self.vacancy {
-            let all_neighbors_to = self.lattice.all_neighbors_to(idx);
-            let other_idx = all_neighbors_to
-                .as_ref()
-                .choose(&mut self.rng)
-                .expect("all atoms have neighbors");
-
-            // This is correct because this model assumes there is no interaction between
-            // a neighboring atom and a vacancy
-            // The value in the grid where the atom sits leads to an over counting
-            // of the energy between index 1 and 2
-            // but this doesnt matter because it is in e_0 and e_1 and thus subtrackted out
-            // !!SEE COMMENT ON Energies IMPLEMENTATION FOR [f32; 4]
-            let e_0 = self.energies_around(*other_idx);
-            self.lattice.swap_vals(idx, *other_idx);
-            let e_1 = self.energies_around(idx);
-
-            let delta_e = e_1 - e_0;
-            if delta_e <= 0.0 || (self.rng.gen::<f32>() < (-beta * delta_e).exp()) {
-                self.update_energy(delta_e);
-                self.vacancy = Some(*other_idx);
-                true
-            } else {
-                self.lattice.swap_vals(idx, *other_idx);
-                false
+        self.move_vacancy_with(|system, delta_e| system.is_accepted(beta, delta_e))
+    }
+
+    /// Like `move_vacancy` but the move is accepted if `accept` returns true for the
+    /// change of energy.
+    pub(crate) fn move_vacancy_with(
+        &mut self,
+        accept: impl FnOnce(&mut Self, f32) -> bool,
+    ) -> bool {
+        if self.vacancies.is_empty() {
+            self.add_vacancy();
+        }
+        let n = match self.vacancies.len() {
+            1 => 0,
+            len => self.rng.gen_range(0..len),
+        };
+        self.move_nth_vacancy_with(n, accept)
+    }
+
+    /// This function moves the vacancies one after the other each time it is called.
+    /// If there is no vacancy in the system yet, one is placed at a random site.
+    pub fn move_next_vacancy(&mut self, beta: f32) -> bool {
+        if self.vacancies.is_empty() {
+            self.add_vacancy();
+        }
+        let n = self.next_vacancy % self.vacancies.len();
+        self.next_vacancy = (n + 1) % self.vacancies.len();
+        self.move_nth_vacancy(n, beta)
+    }
+
+    /// This function moves the n-th vacancy to a random neighboring site.
+    /// The vacancy is treated like any other species, so its bond energies are the ones
+    /// returned by the `Energies` of this system.
+    /// Moves onto another vacancy are rejected.
+    pub fn move_nth_vacancy(&mut self, n: usize, beta: f32) -> bool {
+        self.move_nth_vacancy_with(n, |system, delta_e| system.is_accepted(beta, delta_e))
+    }
+
+    /// Like `move_nth_vacancy` but the move is accepted if `accept` returns true for the
+    /// change of energy.
+    pub(crate) fn move_nth_vacancy_with(
+        &mut self,
+        n: usize,
+        accept: impl FnOnce(&mut Self, f32) -> bool,
+    ) -> bool {
+        let idx = self.vacancies[n];
+        let all_neighbors_to = self.lattice.all_neighbors_to(idx);
+        let other_idx = *all_neighbors_to
+            .as_ref()
+            .choose(&mut self.rng)
+            .expect("all atoms have neighbors");
+        if self.lattice[other_idx].is_vacancy() {
+            return false;
+        }
+
+        // The bond between idx and other_idx is counted twice in e_0 and e_1,
+        // this is fine because its energy doesn't change by the swap.
+        let e_0 = self.energies_around(idx) + self.energies_around(other_idx);
+        self.swap_sites(idx, other_idx);
+        let e_1 = self.energies_around(idx) + self.energies_around(other_idx);
+
+        let delta_e = e_1 - e_0;
+        if accept(self, delta_e) {
+            self.update_energy(delta_e);
+            self.vacancies[n] = other_idx;
+            true
+        } else {
+            self.swap_sites(idx, other_idx);
+            false
+        }
+    }
+
+    /// This function changes the species of the atom at a random site,
+    /// which allows the composition to change (semi grand canonical ensemble).
+    /// `chemical_potentials[i]` is the chemical potential of the i-th species of `all_atoms`,
+    /// only the differences between them matter.
+    /// The move is accepted with the change of E - sum(mu_i * N_i).
+    pub fn flip_species(&mut self, beta: f32, chemical_potentials: &[f32]) -> bool {
+        assert_eq!(
+            chemical_potentials.len(),
+            self.species.len(),
+            "there has to be one chemical potential per species"
+        );
+        self.flip_species_with(|system, delta_e, (i_old, i_new)| {
+            let delta_omega = delta_e - (chemical_potentials[i_new] - chemical_potentials[i_old]);
+            system.is_accepted(beta, delta_omega)
+        })
+    }
+
+    /// Like `flip_species` but the flip is accepted if `accept` returns true for the
+    /// change of energy and the positions of the old and new species in `all_atoms`.
+    pub(crate) fn flip_species_with(
+        &mut self,
+        accept: impl FnOnce(&mut Self, f32, (usize, usize)) -> bool,
+    ) -> bool {
+        assert!(
+            self.species.len() > 1,
+            "there is no other species to flip to"
+        );
+        let idx = loop {
+            let idx = self.lattice.random_idx(&mut self.rng);
+            if !self.lattice[idx].is_vacancy() {
+                break idx;
             }
+        };
+        let old = self.lattice[idx];
+        let i_old = self.species_index(old);
+        // choose one of the other species uniformly
+        let mut i_new = self.rng.gen_range(0..self.species.len() - 1);
+        if i_new >= i_old {
+            i_new += 1;
+        }
+
+        let e_0 = self.energies_around(idx);
+        self.set_site(idx, self.species[i_new]);
+        let e_1 = self.energies_around(idx);
+
+        let delta_e = e_1 - e_0;
+        if accept(self, delta_e, (i_old, i_new)) {
+            self.update_energy(delta_e);
+            true
         } else {
+            self.set_site(idx, old);
+            false
+        }
+    }
+
+    /// This function decides with the acceptance rule of the system if a move with the
+    /// change of energy delta_e is accepted.
+    fn is_accepted(&mut self, beta: f32, delta_e: f32) -> bool {
+        match self.acceptance_rule {
+            AcceptanceRule::Metropolis => {
+                delta_e <= 0.0 || (self.rng.gen::<f32>() < (-beta * delta_e).exp())
+            }
+            _ => self.rng.gen::<f32>() < self.acceptance_rule.probability(beta, delta_e),
+        }
+    }
+
+    /// This function replaces the atom at a random site, which is not a vacancy yet,
+    /// with a vacancy.
+    fn add_vacancy(&mut self) {
+        let idx = loop {
+            let idx = self.lattice.random_idx(&mut self.rng);
+            if !self.lattice[idx].is_vacancy() {
+                break idx;
+            }
+        };
+        let e_0 = self.energies_around(idx);
+        self.set_site(idx, L::Atom::vacancy());
+        let e_1 = self.energies_around(idx);
+        self.update_energy(e_1 - e_0);
+        self.vacancies.push(idx);
+    }
+}
+
+/// driving the moves
+impl<L: Lattice, E: Energies<L::Atom>> System<L, E> {
+    /// This function calls `step` with beta = 1/(k_B * T) for every step of the schedule,
+    /// `step` can perform any move or combination of moves.
+    /// Every observer is called before every `interval`-th step, for example to log or to
+    /// record frames.
+    pub fn anneal(
+        &mut self,
+        schedule: &impl TemperatureSchedule,
+        mut step: impl FnMut(&mut Self, f32),
+        observers: &mut [Observer<'_, Self>],
+    ) {
+        assert!(
+            observers.iter().all(|(interval, _)| *interval > 0),
+            "the intervals of the observers have to be positive"
+        );
+        for i in 0..schedule.steps() {
+            let temp = schedule.temp(i);
+            for (interval, observer) in observers.iter_mut() {
+                if i % *interval == 0 {
+                    observer(self, i, temp);
+                }
+            }
+            step(self, 1.0 / temp);
+        }
+    }
+
+    /// This function calls `step` with beta until the internal energy is equilibrated,
+    /// but at most `max_steps` times, and returns the number of performed steps.
+    /// The energy is sampled every `interval` steps. The system is equilibrated once the start
+    /// of the equilibrated part, see `detect_equilibration`, lies in the first half of the samples.
+    pub fn equilibrate(
+        &mut self,
+        beta: f32,
+        mut step: impl FnMut(&mut Self, f32),
+        interval: usize,
+        max_steps: usize,
+    ) -> usize {
+        assert!(interval > 0);
+        let mut energies = Vec::new();
+        let mut next_check = 32;
+        let mut steps = 0;
+        while steps < max_steps {
+            for _ in 0..interval {
+                step(self, beta);
+            }
+            steps += interval;
+            energies.push(self.internal_energy());
+            // checking less often for long series keeps the detection cheap
+            if energies.len() == next_check {
+                next_check += next_check / 4;
+                let equilibration = detect_equilibration(&energies, energies.len() / 64 + 1);
+                if equilibration.start() * 2 <= energies.len() {
+                    break;
+                }
+            }
+        }
+        steps
+    }
+
+    /// This function calls `step` with beta `steps` times and records the internal energy
+    /// after every `interval`-th step in a histogram with bins of `bin_width`,
+    /// see `DensityOfStates` for the reweighting.
+    pub fn record_energy_histogram(
+        &mut self,
+        beta: f32,
+        mut step: impl FnMut(&mut Self, f32),
+        steps: usize,
+        interval: usize,
+        bin_width: f32,
+    ) -> EnergyHistogram {
+        assert!(interval > 0);
+        let mut histogram = EnergyHistogram::new(beta, bin_width);
+        for i in 1..=steps {
+            step(self, beta);
+            if i % interval == 0 {
+                histogram.add_value(self.internal_energy());
+            }
+        }
+        histogram
+    }
+}
+
+/// kinetic monte carlo
+impl<L: Lattice, E: Energies<L::Atom>> System<L, E> {
+    /// This function performs a rejection free (residence time) step of the vacancies and
+    /// returns the time the step took.
+    /// Every hop of a vacancy to a neighboring atom is an event with the rate
+    /// exp(-beta * (barriers[i] + delta_e / 2)), where `barriers[i]` is the activation barrier
+    /// of the i-th species of `all_atoms` and `delta_e` the change of energy by the hop.
+    /// The time is measured in units of the inverse attempt frequency.
+    /// The time is kept as a f64, so it is infinite once all barriers times beta exceed about 700.
+    /// If there is no vacancy in the system yet, one is placed at a random site.
+    pub fn kinetic_vacancy_hop(&mut self, beta: f32, barriers: &[f32]) -> f64 {
+        assert_eq!(
+            barriers.len(),
+            self.species.len(),
+            "there has to be one barrier per species"
+        );
+        if self.vacancies.is_empty() {
+            self.add_vacancy();
+        }
+        let mut events = Vec::new();
+        for n in 0..self.vacancies.len() {
+            let idx = self.vacancies[n];
+            for other_idx in self.lattice.all_neighbors_to(idx).as_ref() {
+                let other = self.lattice[*other_idx];
+                if other.is_vacancy() {
+                    continue;
+                }
+                let delta_e = self.swap_energy(idx, *other_idx);
+                let barrier = barriers[self.species_index(other)] + delta_e / 2.0;
+                events.push((n, *other_idx, delta_e, -beta as f64 * barrier as f64));
+            }
+        }
+        assert!(
+            !events.is_empty(),
+            "none of the vacancies has a neighboring atom"
+        );
+        // the rates are shifted by the largest one, at low temperatures they would underflow
+        let max_log_rate = events
+            .iter()
+            .map(|(_, _, _, log_rate)| *log_rate)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let mut tot_rate = 0.0;
+        let cumulative_rates: Vec<f64> = events
+            .iter()
+            .map(|(_, _, _, log_rate)| {
+                tot_rate += (log_rate - max_log_rate).exp();
+                tot_rate
+            })
+            .collect();
+
+        let r = self.rng.gen::<f64>() * tot_rate;
+        let event = cumulative_rates
+            .iter()
+            .position(|cumulative_rate| r < *cumulative_rate)
+            .unwrap_or(events.len() - 1);
+        let (n, other_idx, delta_e, _) = events[event];
+        self.swap_sites(self.vacancies[n], other_idx);
+        self.update_energy(delta_e);
+        self.vacancies[n] = other_idx;
+
+        // the time step is exp(ln(-ln(1 - gen)) - ln(tot_rate) - max_log_rate),
+        // it is combined in logs so it is finite whenever the result fits into a f64
+        let ln_delta_t = (-(1.0 - self.rng.gen::<f64>()).ln()).ln() - tot_rate.ln() - max_log_rate;
+        let delta_t = ln_delta_t.exp();
+        self.time += delta_t;
+        delta_t
+    }
+
+    /// the simulated time of all kinetic steps so far
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn reset_time(&mut self) {
+        self.time = 0.0;
+    }
+
+    /// This function returns the change of energy if the atoms at idx_1 and idx_2 were swapped.
+    /// The lattice is swapped back, so the site lists don't need to be updated.
+    fn swap_energy(&mut self, idx_1: L::Index, idx_2: L::Index) -> f32 {
+        let e_0 = self.energies_around(idx_1) + self.energies_around(idx_2);
+        self.lattice.swap_vals(idx_1, idx_2);
+        let e_1 = self.energies_around(idx_1) + self.energies_around(idx_2);
+        self.lattice.swap_vals(idx_1, idx_2);
+        e_1 - e_0
+    }
+}
+
+/// the lists of the sites of every species
+/// All changes to the lattice which are kept have to go through `swap_sites` or `set_site`.
+impl<L: Lattice, E: Energies<L::Atom>> System<L, E> {
+    /// This function swaps the atoms at idx_1 and idx_2.
+    fn swap_sites(&mut self, idx_1: L::Index, idx_2: L::Index) {
+        let flat_1 = self.lattice.flat_index(idx_1);
+        let flat_2 = self.lattice.flat_index(idx_2);
+        let i_1 = self.species_position(self.lattice[idx_1]);
+        let i_2 = self.species_position(self.lattice[idx_2]);
+        if i_1 != i_2 {
+            let p_1 = self.site_positions[flat_1];
+            let p_2 = self.site_positions[flat_2];
+            if let Some(i_1) = i_1 {
+                self.species_sites[i_1][p_1] = flat_2;
+                self.site_positions[flat_2] = p_1;
+            }
+            if let Some(i_2) = i_2 {
+                self.species_sites[i_2][p_2] = flat_1;
+                self.site_positions[flat_1] = p_2;
+            }
+        }
+        self.lattice.swap_vals(idx_1, idx_2);
+    }
+
+    /// This function replaces the atom at idx with atom.
+    fn set_site(&mut self, idx: L::Index, atom: L::Atom) {
+        let flat = self.lattice.flat_index(idx);
+        if let Some(i) = self.species_position(self.lattice[idx]) {
+            let p = self.site_positions[flat];
+            let last = self.species_sites[i]
+                .pop()
+                .expect("the site is in the list");
+            if last != flat {
+                self.species_sites[i][p] = last;
+                self.site_positions[last] = p;
+            }
+        }
+        if let Some(i) = self.species_position(atom) {
+            self.site_positions[flat] = self.species_sites[i].len();
+            self.species_sites[i].push(flat);
+        }
+        self.lattice[idx] = atom;
+    }
+
+    /// This function chooses a pair of atoms of different species uniformly.
+    fn choose_unlike_pair(&mut self) -> Result<(L::Index, L::Index), NoUnlikePairs> {
+        let counts = self.species_counts();
+        let tot: usize = (0..counts.len())
+            .flat_map(|i| (i + 1..counts.len()).map(move |j| (i, j)))
+            .map(|(i, j)| counts[i] * counts[j])
+            .sum();
+        if tot == 0 {
+            return Err(NoUnlikePairs);
+        }
+        let mut r = self.rng.gen_range(0..tot);
+        for i in 0..counts.len() {
+            for j in i + 1..counts.len() {
+                let pairs = counts[i] * counts[j];
+                if r < pairs {
+                    // r is uniform in 0..pairs, so both parts are uniform and independent
+                    let flat_1 = self.species_sites[i][r % counts[i]];
+                    let flat_2 = self.species_sites[j][r / counts[i]];
+                    return Ok((self.idxs[flat_1], self.idxs[flat_2]));
+                }
+                r -= pairs;
+            }
+        }
+        unreachable!("r is smaller than the number of pairs")
+    }
+
+    /// the position of the atom in `all_atoms`, None for vacancies
+    fn species_position(&self, atom: L::Atom) -> Option<usize> {
+        self.species.iter().position(|species| *species == atom)
+    }
+
+    /// the position of the atom in `all_atoms`
+    fn species_index(&self, atom: L::Atom) -> usize {
+        self.species_position(atom)
+            .expect("all atoms are in all_atoms")
+    }
+}
+
+/// all cluster moves
+/// These use the ising mapping of the energies with the spin s = 1 for atom 0 and s = -1 for
+/// atom 1, where the energy of a bond is `coupling * s_1 * s_2` plus terms of the single atoms.
+impl<L: Lattice<Atom = BinAtom>> System<L, [f32; 4]> {
+    /// This function performs a wolff single cluster update with the boltzman factor beta.
+    /// The cluster is grown from a random atom and flipped as a whole,
+    /// the energy not covered by the coupling is accepted with the acceptance rule.
+    /// This changes the composition of the system.
+    pub fn wolff_cluster_flip(&mut self, beta: f32) -> bool {
+        let idx = loop {
             let idx = self.lattice.random_idx(&mut self.rng);
-            self.vacancy = Some(idx);
-            self.lattice[idx] = L::Atom::vacancy();
-            self.move_vacancy(beta)
+            if !self.lattice[idx].is_vacancy() {
+                break idx;
+            }
+        };
+        let coupling = self.ising_coupling();
+        let p_bond = 1.0 - (-2.0 * beta * coupling.abs()).exp();
+        // Safety: all atoms of the cluster are unmarked below
+        let cluster = unsafe { self.grow_ising_cluster(idx, coupling, p_bond) };
+
+        let mut delta_rest = 0.0;
+        let mut delta_coupling = 0.0;
+        for idx in &cluster {
+            delta_rest += self.single_site_flip_energy(*idx, coupling);
+            let s = spin(self.lattice[*idx]);
+            for other_idx in self.lattice.all_neighbors_to(*idx).as_ref() {
+                let other = self.lattice[*other_idx];
+                if !other.is_marked() && !other.is_vacancy() {
+                    delta_coupling -= 2.0 * coupling * s * spin(other);
+                }
+            }
+        }
+        for idx in &cluster {
+            self.lattice[*idx].unmark();
+        }
+
+        if self.is_accepted(beta, delta_rest) {
+            for idx in cluster {
+                self.set_site(idx, BinAtom::new(1 - *self.lattice[idx]));
+            }
+            self.update_energy(delta_rest + delta_coupling);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// This function performs a swendsen wang update of the whole lattice with the boltzman
+    /// factor beta and returns the number of flipped clusters.
+    /// Every cluster is flipped with the heat bath probability of the energy not covered by
+    /// the coupling.
+    /// This changes the composition of the system.
+    pub fn swendsen_wang_sweep(&mut self, beta: f32) -> usize {
+        let coupling = self.ising_coupling();
+        let p_bond = 1.0 - (-2.0 * beta * coupling.abs()).exp();
+        let mut flipped = 0;
+        let mut to_flip = Vec::new();
+        for idx in self.lattice.all_idxs() {
+            let atom = self.lattice[idx];
+            if atom.is_marked() || atom.is_vacancy() {
+                continue;
+            }
+            // Safety: all atoms are unmarked after the loop
+            let cluster = unsafe { self.grow_ising_cluster(idx, coupling, p_bond) };
+            // the energy change of a single site doesn't depend on the other clusters
+            let delta_rest: f32 = cluster
+                .iter()
+                .map(|idx| self.single_site_flip_energy(*idx, coupling))
+                .sum();
+            if self.rng.gen::<f32>() < 1.0 / (1.0 + (beta * delta_rest).exp()) {
+                to_flip.extend(cluster);
+                flipped += 1;
+            }
+        }
+        self.lattice
+            .as_flat_slice_mut()
+            .iter_mut()
+            .for_each(|atom| atom.unmark());
+        for idx in to_flip {
+            self.set_site(idx, BinAtom::new(1 - *self.lattice[idx]));
+        }
+        self.recalculate_internal_energy();
+        flipped
+    }
+
+    fn ising_coupling(&self) -> f32 {
+        let e = self.bond_energies;
+        (e[0] + e[3] - e[1] - e[2]) / 4.0
+    }
+
+    /// This function returns the change of energy if the atom at idx is flipped,
+    /// without the change of the coupling energy of bonds to other atoms.
+    /// This part of the energy only depends on the atom at idx.
+    fn single_site_flip_energy(&self, idx: L::Index, coupling: f32) -> f32 {
+        let mut atom = self.lattice[idx];
+        atom.unmark();
+        let flipped = BinAtom::new(1 - *atom);
+        let mut delta_e = 0.0;
+        for other_idx in self.lattice.all_neighbors_to(idx).as_ref() {
+            let mut other = self.lattice[*other_idx];
+            other.unmark();
+            delta_e += self.bond_energies.get_interaction_energy(flipped, other)
+                - self.bond_energies.get_interaction_energy(atom, other);
+            if !other.is_vacancy() {
+                delta_e += 2.0 * coupling * spin(atom) * spin(other);
+            }
+        }
+        // walls are fixed so all of their energy is a single site term
+        for wall in self.lattice.wall_neighbors_to(idx) {
+            delta_e += self.bond_energies.get_interaction_energy(flipped, wall)
+                - self.bond_energies.get_interaction_energy(atom, wall);
+        }
+        delta_e
+    }
+
+    /// This function grows a cluster like `ClusterCounter::mark_region_and_get_size`,
+    /// but only over bonds with a favorable coupling which are added with probability p_bond.
+    /// # Safety
+    /// all atoms of the returned cluster are marked, this has to be undone using .unmark()
+    unsafe fn grow_ising_cluster(
+        &mut self,
+        idx: L::Index,
+        coupling: f32,
+        p_bond: f32,
+    ) -> Vec<L::Index> {
+        // Safety: the unsafe is passed to the caller
+        unsafe { self.lattice[idx].mark() };
+        let mut cluster = vec![idx];
+        let mut stack = vec![idx];
+        while let Some(idx) = stack.pop() {
+            let s = spin(self.lattice[idx]);
+            for other_idx in self.lattice.all_neighbors_to(idx).as_ref() {
+                let other = self.lattice[*other_idx];
+                if !other.is_marked()
+                    && !other.is_vacancy()
+                    && coupling * s * spin(other) < 0.0
+                    && self.rng.gen::<f32>() < p_bond
+                {
+                    // Safety: the unsafe is passed to the caller
+                    unsafe { self.lattice[*other_idx].mark() };
+                    cluster.push(*other_idx);
+                    stack.push(*other_idx);
+                }
+            }
         }
+        cluster
+    }
+}
+
+/// the ising spin of a possibly marked atom, vacancies have to be excluded beforehand
+fn spin(mut atom: BinAtom) -> f32 {
+    atom.unmark();
+    1.0 - 2.0 * *atom as f32
+}
+
+impl<L: Lattice, E: Energies<L::Atom>> System<L, E> {
+    /// the Warren-Cowley parameters of all neighbor shells of the lattice
+    pub fn short_range_order(&self) -> ShortRangeOrder<L::Atom> {
+        ShortRangeOrder::new(&self.lattice)
+    }
+
+    /// the value of the order parameter for the current configuration
+    pub fn order_parameter(&self, order_parameter: &OrderParameter<L::Atom>) -> f32 {
+        order_parameter.value(&self.lattice)
     }
 }
 
@@ -163,6 +900,20 @@ impl<L: GifFrame, E: Energies<L::Atom>> System<L, E> {
     }
 }
 
+impl<L: Geometry, E: Energies<L::Atom>> System<L, E> {
+    /// the pair correlation functions up to `max_distance`, see `PairCorrelation::new`
+    pub fn pair_correlation(&self, max_distance: f32, bin_width: f32) -> PairCorrelation<L::Atom> {
+        PairCorrelation::new(&self.lattice, max_distance, bin_width)
+    }
+}
+
+impl<L: Grid, E: Energies<L::Atom>> System<L, E> {
+    /// the structure factor of the occupation of `atom`
+    pub fn structure_factor(&self, atom: L::Atom) -> StructureFactor {
+        StructureFactor::new(&self.lattice, atom)
+    }
+}
+
 impl<L: ClusterCounter, E: Energies<L::Atom>> System<L, E>
 where
     <L as Lattice>::Atom: Mark,
@@ -178,3 +929,63 @@ where
         self.lattice.count_clusters(atom)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Array2d, BinConcentration, Boundary, FastArray, MultiAtom, MultiConcentration,
+        PairEnergies, VacancyEnergies,
+    };
+
+    /// moves the vacancies and compares the incremental energy with the recalculated one
+    fn assert_vacancy_moves_keep_energy<L: Lattice, E: Energies<L::Atom>>(
+        system: &mut System<L, E>,
+    ) {
+        for beta in [0.1, 1.0, 10.0] {
+            for i in 0..20_000 {
+                if i % 2 == 0 {
+                    system.move_vacancy(beta);
+                } else {
+                    system.move_next_vacancy(beta);
+                }
+            }
+            let incremental = system.internal_energy();
+            let recalculated = system.recalculate_internal_energy();
+            assert!(
+                (incremental - recalculated).abs() <= 1e-3 * recalculated.abs().max(1.0),
+                "incremental energy {} differs from {}",
+                incremental,
+                recalculated
+            );
+        }
+    }
+
+    #[test]
+    fn vacancy_moves_keep_energy_binary() {
+        let energies = VacancyEnergies::new([-1.0, -0.75, -0.75, -1.0], [0.3, -0.2], 0.5);
+        let mut system = System::<FastArray<BinAtom, 16, 4>, _>::new(
+            energies,
+            Some("vacancies"),
+            BinConcentration::new(1.0, 1.0).with_vacancies(0.05),
+        );
+        assert_vacancy_moves_keep_energy(&mut system);
+    }
+
+    #[test]
+    fn vacancy_moves_keep_energy_walls() {
+        let energies = VacancyEnergies::new(
+            PairEnergies::new([[-1.0, -0.5, 0.2], [-0.5, -0.8, 0.1], [0.2, 0.1, -0.6]]),
+            [0.4, -0.3, 0.1],
+            0.2,
+        );
+        let mut system = System::<Array2d<MultiAtom<3>, 12, 10>, _>::with_shape(
+            energies,
+            Some("walls"),
+            MultiConcentration::new([1.0, 1.0, 1.0]),
+            [Boundary::Wall(MultiAtom::new(1)), Boundary::Free],
+        )
+        .with_vacancies(3);
+        assert_vacancy_moves_keep_energy(&mut system);
+    }
+}
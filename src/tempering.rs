@@ -0,0 +1,130 @@
+use rand::{Rng, SeedableRng};
+use rand_seeder::Seeder;
+use rayon::prelude::*;
+
+use crate::{Energies, Lattice, MyRng, System};
+
+/// Parallel tempering of several systems at a ladder of temperatures.
+/// The replica at position `i` is always at `temps[i]`, exchanges swap the configurations
+/// of neighboring temperatures.
+pub struct ReplicaExchange<L: Lattice, E: Energies<L::Atom>> {
+    replicas: Vec<System<L, E>>,
+    temps: Vec<f32>,
+    rng: MyRng,
+    attempts: Vec<u32>,
+    accepted: Vec<u32>,
+    odd_pairs: bool,
+}
+
+impl<L: Lattice, E: Energies<L::Atom>> ReplicaExchange<L, E> {
+    pub fn new(replicas: Vec<System<L, E>>, temps: Vec<f32>, seed: Option<&str>) -> Self {
+        assert_eq!(
+            replicas.len(),
+            temps.len(),
+            "there has to be one replica per temperature"
+        );
+        assert!(!replicas.is_empty(), "there has to be at least one replica");
+        let rng = match seed {
+            Some(seed) => Seeder::from(seed).make_rng(),
+            None => MyRng::from_entropy(),
+        };
+        let pairs = temps.len() - 1;
+        Self {
+            replicas,
+            temps,
+            rng,
+            attempts: vec![0; pairs],
+            accepted: vec![0; pairs],
+            odd_pairs: false,
+        }
+    }
+
+    pub fn temps(&self) -> &[f32] {
+        &self.temps
+    }
+
+    /// the replicas in the order of the temperatures
+    pub fn replicas(&self) -> &[System<L, E>] {
+        &self.replicas
+    }
+
+    pub fn replicas_mut(&mut self) -> &mut [System<L, E>] {
+        &mut self.replicas
+    }
+
+    /// This function calls `step` `steps` times on every replica with its beta = 1/(k_B * T),
+    /// the replicas run in parallel.
+    pub fn run(&mut self, steps: usize, step: impl Fn(&mut System<L, E>, f32) + Sync)
+    where
+        System<L, E>: Send,
+    {
+        self.replicas
+            .par_iter_mut()
+            .zip(self.temps.par_iter())
+            .for_each(|(system, temp)| {
+                let beta = 1.0 / temp;
+                for _ in 0..steps {
+                    step(system, beta)
+                }
+            });
+    }
+
+    /// This function attempts to exchange the configurations of neighboring temperatures.
+    /// The even and the odd pairs are attempted alternately so every replica takes part in
+    /// at most one exchange per call.
+    /// It returns the number of accepted exchanges.
+    pub fn exchange(&mut self) -> usize {
+        let mut count = 0;
+        let start = if self.odd_pairs { 1 } else { 0 };
+        self.odd_pairs = !self.odd_pairs;
+        for i in (start..self.attempts.len()).step_by(2) {
+            let delta_beta = 1.0 / self.temps[i] - 1.0 / self.temps[i + 1];
+            let delta_e =
+                self.replicas[i].internal_energy() - self.replicas[i + 1].internal_energy();
+            let exponent = delta_beta * delta_e;
+            self.attempts[i] += 1;
+            if exponent >= 0.0 || self.rng.gen::<f32>() < exponent.exp() {
+                self.replicas.swap(i, i + 1);
+                self.accepted[i] += 1;
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// This function alternates `run` with `steps_per_exchange` and `exchange` `exchanges` times.
+    pub fn run_with_exchanges(
+        &mut self,
+        exchanges: usize,
+        steps_per_exchange: usize,
+        step: impl Fn(&mut System<L, E>, f32) + Sync,
+    ) where
+        System<L, E>: Send,
+    {
+        for _ in 0..exchanges {
+            self.run(steps_per_exchange, &step);
+            self.exchange();
+        }
+    }
+
+    /// the fraction of accepted exchanges between temps[i] and temps[i + 1] at position i
+    pub fn acceptance_rates(&self) -> Vec<f32> {
+        self.attempts
+            .iter()
+            .zip(&self.accepted)
+            .map(|(attempts, accepted)| match attempts {
+                0 => 0.0,
+                attempts => *accepted as f32 / *attempts as f32,
+            })
+            .collect()
+    }
+
+    pub fn reset_acceptance_rates(&mut self) {
+        self.attempts.fill(0);
+        self.accepted.fill(0);
+    }
+
+    pub fn into_replicas(self) -> Vec<System<L, E>> {
+        self.replicas
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::HashMap,
+    ops::{Index, IndexMut},
+};
+
+use crate::{Array2d, BinAtom, Geometry, GifFrame, Lattice, MyRng, RandAtom};
+
+/// The offsets to the nearest neighbors and for every further shell,
+/// one offset of each pair (o, -o).
+const SHELL_OFFSETS: [&[(isize, isize)]; 3] = [
+    &[(1, 0), (0, 1), (1, -1)],
+    &[(1, 1), (2, -1), (1, -2)],
+    &[(2, 0), (0, 2), (2, -2)],
+];
+
+/// A triangular lattice where every site has 6 nearest neighbors.
+/// The sites are indexed with axial coordinates (x, y) on top of an `Array2d`,
+/// thus the indexes "wrap around" the same way.
+#[derive(Clone)]
+pub struct Triangular<T, const W: usize, const H: usize>(Array2d<T, W, H>);
+
+impl<T: Copy + Default, const W: usize, const H: usize> Triangular<T, W, H> {
+    pub fn new() -> Self {
+        Self(Array2d::new())
+    }
+}
+
+impl<T, const W: usize, const H: usize> Index<(isize, isize)> for Triangular<T, W, H> {
+    type Output = T;
+
+    fn index(&self, index: (isize, isize)) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<T, const W: usize, const H: usize> IndexMut<(isize, isize)> for Triangular<T, W, H> {
+    fn index_mut(&mut self, index: (isize, isize)) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl<T: Default + Copy, const W: usize, const H: usize> Default for Triangular<T, W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + RandAtom, const W: usize, const H: usize> Lattice for Triangular<T, W, H> {
+    type Atom = T;
+    type Index = (isize, isize);
+    type Neighbors = [Self::Index; 6];
+    type Shape = ();
+
+    fn fill_value(_shape: Self::Shape, val: Self::Atom) -> Self {
+        Self(Array2d::fill_value(Default::default(), val))
+    }
+
+    fn fill_with_fn(_shape: Self::Shape, func: &mut impl FnMut(Self::Index) -> Self::Atom) -> Self {
+        Self(Array2d::fill_with_fn(Default::default(), func))
+    }
+
+    fn shape(&self) -> Self::Shape {}
+
+    fn all_neighbors(&self) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        self.all_shell_neighbors(0)
+    }
+
+    fn all_neighbors_to(&self, idx: Self::Index) -> Self::Neighbors {
+        let (x, y) = idx;
+        [
+            (x + 1, y),
+            (x - 1, y),
+            (x, y + 1),
+            (x, y - 1),
+            (x + 1, y - 1),
+            (x - 1, y + 1),
+        ]
+    }
+
+    fn shells(&self) -> usize {
+        SHELL_OFFSETS.len()
+    }
+
+    fn all_shell_neighbors(&self, shell: usize) -> HashMap<(Self::Atom, Self::Atom), u32> {
+        let mut out = HashMap::new();
+        for x in 0..W as isize {
+            for y in 0..H as isize {
+                for (d_x, d_y) in SHELL_OFFSETS[shell] {
+                    out.entry((self[(x, y)], self[(x - d_x, y - d_y)]))
+                        .and_modify(|e| *e += 1)
+                        .or_insert(1);
+                }
+            }
+        }
+        out
+    }
+
+    fn shell_neighbors_to(&self, idx: Self::Index, shell: usize) -> Vec<Self::Index> {
+        SHELL_OFFSETS[shell]
+            .iter()
+            .flat_map(|(d_x, d_y)| [(idx.0 + d_x, idx.1 + d_y), (idx.0 - d_x, idx.1 - d_y)])
+            .collect()
+    }
+
+    fn all_idxs(&self) -> Vec<Self::Index> {
+        self.0.all_idxs()
+    }
+
+    fn tot_sites(&self) -> usize {
+        W * H
+    }
+
+    fn as_flat_slice(&self) -> &[Self::Atom] {
+        self.0.as_flat_slice()
+    }
+
+    fn as_flat_slice_mut(&mut self) -> &mut [Self::Atom] {
+        self.0.as_flat_slice_mut()
+    }
+
+    fn flat_index(&self, idx: Self::Index) -> usize {
+        self.0.flat_index(idx)
+    }
+
+    fn random_idx(&self, rng: &mut MyRng) -> Self::Index {
+        self.0.random_idx(rng)
+    }
+
+    fn reduce_index(&self, idx: Self::Index) -> Self::Index {
+        self.0.reduce_index(idx)
+    }
+
+    fn translate(&self, idx: Self::Index, by: Self::Index) -> Option<Self::Index> {
+        self.0.translate(idx, by)
+    }
+}
+
+impl<T: Copy + RandAtom, const W: usize, const H: usize> Geometry for Triangular<T, W, H> {
+    fn coordinates(&self, idx: Self::Index) -> [f32; 3] {
+        self.0.coordinates(idx)
+    }
+
+    /// the nearest neighbors have the distance 1
+    fn axes(&self) -> [[f32; 3]; 3] {
+        [
+            [1.0, 0.0, 0.0],
+            [0.5, 3.0_f32.sqrt() / 2.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ]
+    }
+
+    fn periods(&self) -> [Option<f32>; 3] {
+        self.0.periods()
+    }
+}
+
+impl<const W: usize, const H: usize> GifFrame for Triangular<BinAtom, W, H> {
+    /// the frame shows the axial coordinates, so the lattice appears sheared
+    fn get_frame(&self) -> gif::Frame<'_> {
+        self.0.get_frame()
+    }
+}
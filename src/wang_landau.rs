@@ -0,0 +1,297 @@
+use rand::{Rng, SeedableRng};
+use rand_seeder::Seeder;
+
+use crate::{prefixed, Energies, Lattice, MyRng, System};
+
+/// The moves the Wang-Landau sampler can use to explore the configurations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WangLandauMove {
+    /// `System::monte_carlo_swap`, the composition is fixed
+    Swap,
+    /// `System::move_vacancy`, the composition is fixed
+    Vacancy,
+    /// `System::flip_species`, all compositions are sampled
+    Flip,
+}
+
+/// A Wang-Landau sampler of the density of states g(E) of a system.
+/// The energies in `[e_min, e_max)` are split into bins of equal width,
+/// moves leaving this range are rejected.
+pub struct WangLandau<L: Lattice, E: Energies<L::Atom>> {
+    system: System<L, E>,
+    movement: WangLandauMove,
+    rng: MyRng,
+    e_min: f32,
+    bin_width: f32,
+    ln_g: Vec<f64>,
+    histogram: Vec<u64>,
+    ln_f: f64,
+    flatness: f64,
+}
+
+impl<L: Lattice, E: Energies<L::Atom>> WangLandau<L, E> {
+    /// The system has to start with an energy in `[e_min, e_max)`.
+    pub fn new(
+        mut system: System<L, E>,
+        movement: WangLandauMove,
+        (e_min, e_max): (f32, f32),
+        bins: usize,
+        seed: Option<&str>,
+    ) -> Self {
+        assert!(e_min < e_max && bins > 0);
+        let energy = system.internal_energy();
+        assert!(
+            (e_min..e_max).contains(&energy),
+            "the energy of the system is not in the energy range"
+        );
+        let rng = match seed {
+            Some(seed) => Seeder::from(seed).make_rng(),
+            None => MyRng::from_entropy(),
+        };
+        Self {
+            system,
+            movement,
+            rng,
+            e_min,
+            bin_width: (e_max - e_min) / bins as f32,
+            ln_g: vec![0.0; bins],
+            histogram: vec![0; bins],
+            ln_f: 1.0,
+            flatness: 0.8,
+        }
+    }
+
+    /// sets the modification factor ln(f) the sampling starts with, the default is 1
+    pub fn with_ln_f(self, ln_f: f64) -> Self {
+        assert!(ln_f > 0.0);
+        Self { ln_f, ..self }
+    }
+
+    /// sets the fraction of the mean every visited bin of the histogram has to reach,
+    /// the default is 0.8
+    pub fn with_flatness(self, flatness: f64) -> Self {
+        assert!((0.0..1.0).contains(&flatness));
+        Self { flatness, ..self }
+    }
+
+    pub fn system(&self) -> &System<L, E> {
+        &self.system
+    }
+
+    pub fn into_system(self) -> System<L, E> {
+        self.system
+    }
+
+    pub fn ln_f(&self) -> f64 {
+        self.ln_f
+    }
+
+    pub fn histogram(&self) -> &[u64] {
+        &self.histogram
+    }
+
+    /// the unnormalized ln(g(E)) of every bin, bins which were never visited are 0
+    pub fn ln_g(&self) -> &[f64] {
+        &self.ln_g
+    }
+
+    /// the energy at the center of every bin
+    pub fn energies(&self) -> Vec<f32> {
+        (0..self.ln_g.len())
+            .map(|i| self.e_min + (i as f32 + 0.5) * self.bin_width)
+            .collect()
+    }
+
+    fn bin(&self, energy: f32) -> Option<usize> {
+        let bin = ((energy - self.e_min) / self.bin_width).floor();
+        if bin >= 0.0 && (bin as usize) < self.ln_g.len() {
+            Some(bin as usize)
+        } else {
+            None
+        }
+    }
+
+    /// This function performs one move and updates g(E) and the histogram at the energy
+    /// after the move.
+    pub fn step(&mut self) -> bool {
+        let current = self.system.internal_energy();
+        let ln_g_0 = self.ln_g[self.bin(current).expect("the energy stays in the range")];
+        // the closure only borrows the fields it uses so the system can be moved at the same time
+        let mut accept = |delta_e: f32| {
+            let bin = ((current + delta_e - self.e_min) / self.bin_width).floor();
+            if bin < 0.0 || bin as usize >= self.ln_g.len() {
+                return false;
+            }
+            let diff = ln_g_0 - self.ln_g[bin as usize];
+            diff >= 0.0 || self.rng.gen::<f64>() < diff.exp()
+        };
+        let accepted = match self.movement {
+            WangLandauMove::Swap => self
+                .system
+                .monte_carlo_swap_with(|_, delta_e| accept(delta_e))
+                .expect("there are atoms of different species"),
+            WangLandauMove::Vacancy => self.system.move_vacancy_with(|_, delta_e| accept(delta_e)),
+            WangLandauMove::Flip => self
+                .system
+                .flip_species_with(|_, delta_e, _| accept(delta_e)),
+        };
+        let energy = self.system.internal_energy();
+        let bin = self.bin(energy).expect("the energy stays in the range");
+        self.ln_g[bin] += self.ln_f;
+        self.histogram[bin] += 1;
+        accepted
+    }
+
+    /// The histogram is flat if every visited bin has at least `flatness` times the mean
+    /// of the visited bins.
+    pub fn is_flat(&self) -> bool {
+        let visited: Vec<u64> = self
+            .ln_g
+            .iter()
+            .zip(&self.histogram)
+            .filter(|(ln_g, _)| **ln_g > 0.0)
+            .map(|(_, count)| *count)
+            .collect();
+        if visited.is_empty() {
+            return false;
+        }
+        let mean = visited.iter().sum::<u64>() as f64 / visited.len() as f64;
+        visited
+            .iter()
+            .all(|count| *count as f64 >= self.flatness * mean)
+    }
+
+    /// This function runs the sampler until ln(f) drops below `ln_f_final`.
+    /// The flatness is checked every `steps_per_check` steps, when the histogram is flat
+    /// ln(f) is halved and the histogram is reset.
+    /// It returns the number of performed steps.
+    pub fn run(&mut self, steps_per_check: usize, ln_f_final: f64) -> usize {
+        let mut steps = 0;
+        while self.ln_f >= ln_f_final {
+            for _ in 0..steps_per_check {
+                self.step();
+            }
+            steps += steps_per_check;
+            if self.is_flat() {
+                self.ln_f /= 2.0;
+                self.histogram.fill(0);
+            }
+        }
+        steps
+    }
+
+    /// ln(g(E)) of the visited bins normalized such that the total number of states is
+    /// exp(ln_states), for example `ln_multinomial` of the species counts for a fixed
+    /// composition.
+    pub fn normalized_ln_g(&self, ln_states: f64) -> Vec<(f32, f64)> {
+        let visited: Vec<(f32, f64)> = self
+            .energies()
+            .into_iter()
+            .zip(self.ln_g.iter().copied())
+            .filter(|(_, ln_g)| *ln_g > 0.0)
+            .collect();
+        let ln_tot = log_sum_exp(visited.iter().map(|(_, ln_g)| *ln_g));
+        visited
+            .into_iter()
+            .map(|(energy, ln_g)| (energy, ln_g - ln_tot + ln_states))
+            .collect()
+    }
+
+    /// This function calculates the thermodynamic quantities at temp from g(E),
+    /// see `normalized_ln_g` for ln_states.
+    pub fn thermodynamics(&self, temp: f32, ln_states: f64) -> Thermodynamics {
+        Thermodynamics::from_ln_g(temp, &self.normalized_ln_g(ln_states))
+    }
+}
+
+/// The thermodynamic quantities at one temperature, with k_B = 1.
+#[derive(Debug, Clone, Copy)]
+pub struct Thermodynamics {
+    temp: f32,
+    energy: f32,
+    heat_capacity: f32,
+    free_energy: f32,
+    entropy: f32,
+}
+
+impl Thermodynamics {
+    /// calculates the thermodynamic quantities at temp from pairs of energies and ln(g(E))
+    pub(crate) fn from_ln_g(temp: f32, ln_g: &[(f32, f64)]) -> Self {
+        let beta = 1.0 / temp as f64;
+        let ln_weights: Vec<f64> = ln_g
+            .iter()
+            .map(|(energy, ln_g)| ln_g - beta * *energy as f64)
+            .collect();
+        let ln_z = log_sum_exp(ln_weights.iter().copied());
+        let mut energy = 0.0;
+        let mut energy_squared = 0.0;
+        for ((e, _), ln_weight) in ln_g.iter().zip(&ln_weights) {
+            let p = (ln_weight - ln_z).exp();
+            energy += p * *e as f64;
+            energy_squared += p * (*e as f64).powi(2);
+        }
+        let free_energy = -ln_z / beta;
+        Self {
+            temp,
+            energy: energy as f32,
+            heat_capacity: ((energy_squared - energy * energy) * beta * beta) as f32,
+            free_energy: free_energy as f32,
+            entropy: ((energy - free_energy) * beta) as f32,
+        }
+    }
+
+    pub fn temp(&self) -> f32 {
+        self.temp
+    }
+
+    pub fn energy(&self) -> f32 {
+        self.energy
+    }
+
+    pub fn heat_capacity(&self) -> f32 {
+        self.heat_capacity
+    }
+
+    pub fn free_energy(&self) -> f32 {
+        self.free_energy
+    }
+
+    pub fn entropy(&self) -> f32 {
+        self.entropy
+    }
+
+    pub fn get_categories(prefix: Option<impl ToString>) -> Vec<String> {
+        prefixed(
+            prefix,
+            vec![
+                "temp".to_owned(),
+                "energy".to_owned(),
+                "heat capacity".to_owned(),
+                "free energy".to_owned(),
+                "entropy".to_owned(),
+            ],
+        )
+    }
+
+    pub fn as_vec_f32(&self) -> Vec<f32> {
+        vec![
+            self.temp,
+            self.energy,
+            self.heat_capacity,
+            self.free_energy,
+            self.entropy,
+        ]
+    }
+}
+
+/// ln(n! / (k_1! * k_2! * ...)) with n = k_1 + k_2 + ..., the number of ways to arrange
+/// atoms with the given counts on the lattice
+pub fn ln_multinomial(counts: &[usize]) -> f64 {
+    let ln_factorial = |n: usize| (1..=n).map(|i| (i as f64).ln()).sum::<f64>();
+    ln_factorial(counts.iter().sum()) - counts.iter().map(|k| ln_factorial(*k)).sum::<f64>()
+}
+
+pub(crate) fn log_sum_exp(values: impl Iterator<Item = f64> + Clone) -> f64 {
+    let max = values.clone().fold(f64::NEG_INFINITY, f64::max);
+    max + values.map(|value| (value - max).exp()).sum::<f64>().ln()
+}